@@ -1,68 +1,1145 @@
 // Chain of Responsibility
 // ref:
 // 1. https://refactoring.guru/design-patterns/chain-of-responsibility/rust/example
-fn main() {
-    let mut cashier = Cashier::default();
-    let mut medical = Medical::new(cashier);
-    let mut doctor = Doctor::new(medical);
-    let mut reception = Reception::new(doctor);
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // A price list can be given on the command line after the chain file.
+    let prices = match std::env::args().nth(2) {
+        Some(path) => PriceList::load(&path)?,
+        None => PriceList::parse(include_str!("prices.txt"))?,
+    };
+
+    let mut hospital = ChainBuilder::new()
+        .then(Reception::default())
+        .then(Doctor::default())
+        .then(Medical::default())
+        .then(Cashier::new(prices))
+        .build()?;
 
     let mut patient = Patient {
         name: "John".to_string(),
+        cash: Money(200 * 100),
         ..Patient::default()
     };
 
-    reception.execute(&mut patient);
+    println!("{result}", result = hospital.execute(&mut patient));
 
     println!("\nThe patient has been already handled:\n");
 
-    reception.execute(&mut patient);
+    println!("{result}", result = hospital.execute(&mut patient));
+
+    // A chain file can be given on the command line.
+    let registry = DepartmentRegistry::default();
+    let mut chain = match std::env::args().nth(1) {
+        Some(path) => ChainBuilder::from_file(&path, &registry)?,
+        None => ChainBuilder::from_config(include_str!("hospital.chain"), &registry)?,
+    }
+    .build()?;
+
+    let mut patient = Patient {
+        name: "Jane".to_string(),
+        cash: Money(200 * 100),
+        insurance: Insurance::Covered,
+        healthy: true,
+        ..Patient::default()
+    };
+
+    println!("\nChain read from a file, with a healthy patient:\n");
+
+    println!("{result}", result = chain.execute(&mut patient));
+
+    let mut patient = Patient {
+        name: "Jim".to_string(),
+        insurance: Insurance::Lapsed,
+        ..Patient::default()
+    };
+
+    println!("\nPatient whose insurance has lapsed:\n");
+
+    println!("{result}", result = chain.execute(&mut patient));
+
+    println!("\nChain changed while in use:\n");
+
+    chain.insert_after("reception", Screening::new("COVID"))?;
+    println!("{chain}", chain = chain.names().join(" -> "));
+
+    let mut patient = Patient {
+        name: "Joe".to_string(),
+        cash: Money(200 * 100),
+        ..Patient::default()
+    };
+    println!("{result}", result = chain.execute(&mut patient));
+
+    chain.replace("screening", Screening::new("flu"))?;
+    let mut patient = Patient {
+        name: "Ann".to_string(),
+        cash: Money(200 * 100),
+        healthy: true,
+        ..Patient::default()
+    };
+    println!("\nScreening for flu instead:\n");
+    println!("{result}", result = chain.execute(&mut patient));
+
+    // Screen patients at the door instead.
+    chain.remove("screening")?;
+    chain.insert_before("reception", Screening::new("flu"))?;
+    println!("\n{chain}", chain = chain.names().join(" -> "));
+    if let Err(error) = chain.insert_after("cashier", Doctor::default()) {
+        println!("Error: {error}");
+    }
+    if let Err(error) = chain.remove("surgeon") {
+        println!("Error: {error}");
+    }
+
+    println!("\nWorkflow where the doctor decides where to go next:\n");
+
+    let mut workflow = WorkflowBuilder::new()
+        .department(Reception::default(), &["doctor"])
+        .department(Doctor::default(), &["medical", "lab"])
+        .department(Lab::default(), &["medical"])
+        .department(Medical::default(), &["cashier"])
+        .department(Cashier::default(), &[])
+        .build()?;
+    print!("{workflow}");
+
+    for (name, needs_lab_test, healthy) in [("Amy", true, false), ("Ben", false, true)] {
+        let mut patient = Patient {
+            name: name.to_string(),
+            needs_lab_test,
+            healthy,
+            cash: Money(200 * 100),
+            ..Patient::default()
+        };
+        println!();
+        println!("{result}", result = workflow.execute(&mut patient));
+    }
+
+    let mut patient = Patient {
+        name: "Bob".to_string(),
+        needs_lab_test: true,
+        ..Patient::default()
+    };
+    println!("\nLinear chain without a lab:\n");
+    println!("{result}", result = hospital.execute(&mut patient));
+
+    let mut patient = Patient {
+        name: "Sam".to_string(),
+        cash: Money(50 * 100),
+        ..Patient::default()
+    };
+    println!("\nPatient who cannot pay everything at once:\n");
+    println!("{result}", result = hospital.execute(&mut patient));
+
+    patient.cash = Money(100 * 100);
+    println!("\nBack with more money:\n");
+    println!("{result}", result = hospital.execute(&mut patient));
+
+    println!("\nWaiting room served by triage:");
+
+    let mut waiting_room = WaitingRoom::default();
+    for (name, triage) in [
+        ("Tom", Triage::Standard),
+        ("Eve", Triage::NonUrgent),
+        ("Max", Triage::Emergency),
+        ("Liz", Triage::Urgent),
+        ("Ray", Triage::Standard),
+    ] {
+        waiting_room.arrive(Patient {
+            name: name.to_string(),
+            triage,
+            cash: Money(200 * 100),
+            ..Patient::default()
+        });
+    }
+    for (patient, result) in waiting_room.serve(&mut hospital) {
+        println!("{name}: {result}", name = patient.name);
+    }
+    println!("{chain}", chain = hospital.names().join(" -> "));
+
+    println!();
+    for broken in ["price lab 45\ncover lab all\n", "price lab 50000000\n"] {
+        if let Err(error) = PriceList::parse(broken) {
+            println!("Error: {error}");
+        }
+    }
+
+    println!("\nWorkflows which are refused:\n");
+
+    let looping = WorkflowBuilder::new()
+        .department(Reception::default(), &["doctor"])
+        .department(Doctor::default(), &["lab", "cashier"])
+        .department(Lab::default(), &["doctor"])
+        .department(Cashier::default(), &[])
+        .build();
+    let dangling = WorkflowBuilder::new()
+        .department(Reception::default(), &["doctor"])
+        .department(Doctor::default(), &["surgeon"])
+        .build();
+    for workflow in [looping, dangling] {
+        if let Err(error) = workflow {
+            println!("Error: {error}");
+        }
+    }
+
+    println!("\nChains which are refused:\n");
+
+    let broken = [
+        "# nobody works here\n",
+        "reception\ndoctor\nreception\n",
+        "reception\nsurgeon\n",
+    ];
+    for config in broken {
+        if let Err(error) =
+            ChainBuilder::from_config(config, &registry).and_then(ChainBuilder::build)
+        {
+            println!("Error: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+enum ChainError {
+    Io(io::Error),
+    Empty,
+    /// The department is in the chain twice, patients would go round in circles.
+    Cycle(&'static str),
+    UnknownDepartment {
+        line: usize,
+        name: String,
+    },
+    NotInChain(String),
+    UnknownSuccessor {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Patients could go round in circles along these departments.
+    Loop(Vec<&'static str>),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Cannot read chain: {error}"),
+            Self::Empty => write!(f, "The chain has no departments"),
+            Self::Cycle(name) => write!(f, "'{name}' is in the chain more than once"),
+            Self::UnknownDepartment { line, name } => {
+                write!(f, "line {line}: no department called '{name}'")
+            }
+            Self::NotInChain(name) => write!(f, "'{name}' is not in the chain"),
+            Self::UnknownSuccessor { from, to } => {
+                write!(f, "'{from}' leads to '{to}', which is not in the workflow")
+            }
+            Self::Loop(departments) => {
+                write!(
+                    f,
+                    "Patients could loop: {path}",
+                    path = departments.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl Error for ChainError {}
+
+impl From<io::Error> for ChainError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Departments a chain file can refer to by name.
+struct DepartmentRegistry {
+    factories: HashMap<&'static str, fn() -> Box<dyn Department>>,
+}
+
+impl Default for DepartmentRegistry {
+    fn default() -> Self {
+        let mut factories: HashMap<&'static str, fn() -> Box<dyn Department>> = HashMap::new();
+        factories.insert("reception", || Box::<Reception>::default());
+        factories.insert("doctor", || Box::<Doctor>::default());
+        factories.insert("lab", || Box::<Lab>::default());
+        factories.insert("medical", || Box::<Medical>::default());
+        factories.insert("cashier", || Box::<Cashier>::default());
+        factories.insert("screening", || Box::new(Screening::new("COVID")));
+
+        Self { factories }
+    }
+}
+
+impl DepartmentRegistry {
+    fn build(&self, name: &str) -> Option<Box<dyn Department>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+}
+
+/// Links departments in the order patients visit them.
+#[derive(Default)]
+struct ChainBuilder {
+    departments: Vec<Box<dyn Department>>,
+}
+
+impl ChainBuilder {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn then(mut self, department: impl Department + 'static) -> Self {
+        self.departments.push(Box::new(department));
+        self
+    }
+
+    /// One department name per line, `#` starts a comment.
+    fn from_config(config: &str, registry: &DepartmentRegistry) -> Result<Self, ChainError> {
+        let mut builder = Self::new();
+        for (index, line) in config.lines().enumerate() {
+            let name = line.split('#').next().unwrap_or_default().trim();
+            if name.is_empty() {
+                continue;
+            }
+
+            let department = registry
+                .build(name)
+                .ok_or_else(|| ChainError::UnknownDepartment {
+                    line: index + 1,
+                    name: name.to_string(),
+                })?;
+            builder.departments.push(department);
+        }
+
+        Ok(builder)
+    }
+
+    fn from_file(path: &str, registry: &DepartmentRegistry) -> Result<Self, ChainError> {
+        Self::from_config(&fs::read_to_string(path)?, registry)
+    }
+
+    fn build(self) -> Result<Chain, ChainError> {
+        let mut seen = Vec::new();
+        for department in &self.departments {
+            if seen.contains(&department.name()) {
+                return Err(ChainError::Cycle(department.name()));
+            }
+            seen.push(department.name());
+        }
+
+        let mut head: Option<Box<dyn Department>> = None;
+        for mut department in self.departments.into_iter().rev() {
+            *department.next() = head;
+            head = Some(department);
+        }
+
+        match head {
+            Some(_) => Ok(Chain { head }),
+            None => Err(ChainError::Empty),
+        }
+    }
+}
+
+/// Linked departments, which can be changed while the chain is in use.
+struct Chain {
+    // Never `None`, the last department cannot be removed.
+    head: Option<Box<dyn Department>>,
+}
+
+impl Chain {
+    fn execute(&mut self, patient: &mut Patient) -> ChainResult {
+        self.head
+            .as_mut()
+            .expect("a chain has departments")
+            .execute(patient)
+    }
+
+    /// Names of the departments in the order patients visit them.
+    fn names(&mut self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        let mut slot = &mut self.head;
+        while let Some(department) = slot {
+            names.push(department.name());
+            slot = department.next();
+        }
+
+        names
+    }
+
+    fn insert_before(
+        &mut self,
+        name: &str,
+        department: impl Department + 'static,
+    ) -> Result<(), ChainError> {
+        self.check_new(department.name(), None)?;
+
+        let slot = self.slot(name)?;
+        let mut department: Box<dyn Department> = Box::new(department);
+        *department.next() = slot.take();
+        *slot = Some(department);
+
+        Ok(())
+    }
+
+    fn insert_after(
+        &mut self,
+        name: &str,
+        department: impl Department + 'static,
+    ) -> Result<(), ChainError> {
+        self.check_new(department.name(), None)?;
+
+        let previous = self.slot(name)?.as_mut().expect("the slot is taken");
+        let mut department: Box<dyn Department> = Box::new(department);
+        *department.next() = previous.next().take();
+        *previous.next() = Some(department);
+
+        Ok(())
+    }
+
+    /// Takes the department out, its successor takes its place.
+    fn remove(&mut self, name: &str) -> Result<Box<dyn Department>, ChainError> {
+        if self.names() == [name] {
+            return Err(ChainError::Empty);
+        }
+
+        let slot = self.slot(name)?;
+        let mut removed = slot.take().expect("the slot is taken");
+        *slot = removed.next().take();
+
+        Ok(removed)
+    }
+
+    /// Puts the department in the place of the named one, which is returned.
+    fn replace(
+        &mut self,
+        name: &str,
+        department: impl Department + 'static,
+    ) -> Result<Box<dyn Department>, ChainError> {
+        self.check_new(department.name(), Some(name))?;
+
+        let slot = self.slot(name)?;
+        let mut replaced = slot.take().expect("the slot is taken");
+        let mut department: Box<dyn Department> = Box::new(department);
+        *department.next() = replaced.next().take();
+        *slot = Some(department);
+
+        Ok(replaced)
+    }
+
+    /// Runs the patient past the deferred departments first and through them
+    /// afterwards, in chain order. The chain is the same again afterwards.
+    fn execute_deferring(&mut self, patient: &mut Patient, deferred: &[&str]) -> ChainResult {
+        let names = self.names();
+        if names.iter().all(|name| deferred.contains(name)) {
+            return self.execute(patient);
+        }
+
+        // From the back, so that the positions before stay the same.
+        let mut taken: Vec<(usize, Box<dyn Department>)> = names
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, name)| deferred.contains(name))
+            .map(|(position, _)| (position, self.take_at(position)))
+            .collect();
+        taken.reverse();
+
+        let mut result = self.execute(patient);
+        for (_, department) in &mut taken {
+            if !matches!(result, ChainResult::Completed { .. }) {
+                break;
+            }
+            // Taken out, the department has no successor.
+            result = department.execute(patient);
+        }
+
+        for (position, department) in taken {
+            self.put_at(position, department);
+        }
+
+        result
+    }
+
+    fn take_at(&mut self, position: usize) -> Box<dyn Department> {
+        let slot = self.slot_at(position);
+        let mut taken = slot.take().expect("the slot is taken");
+        *slot = taken.next().take();
+
+        taken
+    }
+
+    fn put_at(&mut self, position: usize, mut department: Box<dyn Department>) {
+        let slot = self.slot_at(position);
+        *department.next() = slot.take();
+        *slot = Some(department);
+    }
+
+    /// The link at `position`, counted from the head.
+    fn slot_at(&mut self, position: usize) -> &mut Option<Box<dyn Department>> {
+        let mut slot = &mut self.head;
+        for _ in 0..position {
+            slot = slot.as_mut().expect("the chain is long enough").next();
+        }
+
+        slot
+    }
+
+    /// Refuses a department which is already in the chain, except in place of
+    /// `replaced`.
+    fn check_new(&mut self, name: &'static str, replaced: Option<&str>) -> Result<(), ChainError> {
+        if replaced != Some(name) && self.names().contains(&name) {
+            return Err(ChainError::Cycle(name));
+        }
+
+        Ok(())
+    }
+
+    /// The link which holds the named department.
+    fn slot(&mut self, name: &str) -> Result<&mut Option<Box<dyn Department>>, ChainError> {
+        if !self.names().contains(&name) {
+            return Err(ChainError::NotInChain(name.to_string()));
+        }
+
+        let mut slot = &mut self.head;
+        while slot
+            .as_ref()
+            .is_some_and(|department| department.name() != name)
+        {
+            slot = slot.as_mut().expect("the slot is taken").next();
+        }
+
+        Ok(slot)
+    }
+}
+
+/// Departments an emergency patient only visits once treated.
+const DEFERRED_IN_EMERGENCY: [&str; 2] = ["reception", "cashier"];
+
+/// Patients queued in front of a chain, served the most urgent first.
+#[derive(Default)]
+struct WaitingRoom {
+    // In the order of arrival.
+    patients: Vec<Patient>,
+}
+
+impl WaitingRoom {
+    fn arrive(&mut self, patient: Patient) {
+        self.patients.push(patient);
+    }
+
+    /// The most urgent patient, of those the one who came first.
+    fn call(&mut self) -> Option<Patient> {
+        let (position, _) = self
+            .patients
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, patient)| patient.triage)?;
+
+        Some(self.patients.remove(position))
+    }
+
+    /// Sends everybody waiting through the chain. Emergencies are treated
+    /// before they are registered and billed.
+    fn serve(&mut self, chain: &mut Chain) -> Vec<(Patient, ChainResult)> {
+        let mut served = Vec::new();
+        while let Some(mut patient) = self.call() {
+            println!(
+                "\nCalling {name} ({triage})",
+                name = patient.name,
+                triage = patient.triage
+            );
+            let result = if patient.triage == Triage::Emergency {
+                chain.execute_deferring(&mut patient, &DEFERRED_IN_EMERGENCY)
+            } else {
+                chain.execute(&mut patient)
+            };
+            served.push((patient, result));
+        }
+
+        served
+    }
+}
+
+/// Builds a workflow where a department can lead to several others.
+#[derive(Default)]
+struct WorkflowBuilder {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    department: Box<dyn Department>,
+    // The first one is taken unless the department picks another.
+    successors: Vec<&'static str>,
+}
+
+impl WorkflowBuilder {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// The first department added is where patients start.
+    fn department(
+        mut self,
+        department: impl Department + 'static,
+        successors: &[&'static str],
+    ) -> Self {
+        self.nodes.push(Node {
+            department: Box::new(department),
+            successors: successors.to_vec(),
+        });
+        self
+    }
+
+    /// Makes sure that every path through the workflow ends.
+    fn build(self) -> Result<Workflow, ChainError> {
+        if self.nodes.is_empty() {
+            return Err(ChainError::Empty);
+        }
+
+        let workflow = Workflow { nodes: self.nodes };
+        for (index, node) in workflow.nodes.iter().enumerate() {
+            let name = node.department.name();
+            if workflow.index(name) != Some(index) {
+                return Err(ChainError::Cycle(name));
+            }
+            if let Some(to) = node
+                .successors
+                .iter()
+                .find(|to| workflow.index(to).is_none())
+            {
+                return Err(ChainError::UnknownSuccessor { from: name, to });
+            }
+        }
+
+        let mut checked = HashSet::new();
+        for index in 0..workflow.nodes.len() {
+            workflow.check_paths(index, &mut Vec::new(), &mut checked)?;
+        }
+
+        Ok(workflow)
+    }
+}
+
+/// Departments linked as a graph without loops, every department picks which
+/// one the patient goes to next.
+struct Workflow {
+    nodes: Vec<Node>,
+}
+
+impl Workflow {
+    fn execute(&mut self, patient: &mut Patient) -> ChainResult {
+        let mut current = 0;
+        loop {
+            let node = &mut self.nodes[current];
+            let name = node.department.name();
+            let next = match node.department.handle(patient) {
+                Outcome::Continue => node.successors.first().copied(),
+                Outcome::Skip(departments) => {
+                    let mut next = node.successors.first().copied();
+                    for _ in 0..departments {
+                        next = next.and_then(|skipped| self.default_successor(skipped));
+                    }
+                    next
+                }
+                Outcome::Branch(to) if node.successors.contains(&to) => Some(to),
+                Outcome::Branch(to) => return ChainResult::Misrouted { by: name, to },
+                Outcome::Stop => return ChainResult::Stopped { by: name },
+                Outcome::Reject(reason) => return ChainResult::Rejected { by: name, reason },
+            };
+
+            match next.and_then(|next| self.index(next)) {
+                Some(next) => current = next,
+                None => return ChainResult::Completed { last: name },
+            }
+        }
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.department.name() == name)
+    }
+
+    fn default_successor(&self, name: &str) -> Option<&'static str> {
+        let node = &self.nodes[self.index(name)?];
+        node.successors.first().copied()
+    }
+
+    /// Fails if a path from the department leads back into `path`. The paths
+    /// from departments in `checked` are known to be fine, so every department
+    /// is only gone through once.
+    fn check_paths(
+        &self,
+        index: usize,
+        path: &mut Vec<&'static str>,
+        checked: &mut HashSet<usize>,
+    ) -> Result<(), ChainError> {
+        if checked.contains(&index) {
+            return Ok(());
+        }
+
+        let node = &self.nodes[index];
+        let name = node.department.name();
+        if let Some(start) = path.iter().position(|visited| *visited == name) {
+            let mut departments = path[start..].to_vec();
+            departments.push(name);
+            return Err(ChainError::Loop(departments));
+        }
+
+        path.push(name);
+        for successor in &node.successors {
+            let next = self.index(successor).expect("successors are checked");
+            self.check_paths(next, path, checked)?;
+        }
+        path.pop();
+        checked.insert(index);
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Workflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.nodes {
+            let name = node.department.name();
+            match node.successors.as_slice() {
+                [] => writeln!(f, "{name} (end)")?,
+                successors => writeln!(f, "{name} -> {next}", next = successors.join(" | "))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a department decided about the patient.
+enum Outcome {
+    Continue,
+    /// Nothing left to do, the patient goes home.
+    Stop,
+    Reject(String),
+    /// The patient passes the next departments without visiting them.
+    // None of the hospital departments knows what comes after it.
+    #[allow(dead_code)]
+    Skip(usize),
+    /// The patient goes on to the named department, one of those after this one.
+    Branch(&'static str),
 }
 
-fn into_next(department: impl Department + 'static + Sized) -> Option<Box<dyn Department>> {
-    Some(Box::new(department))
+/// Where and why the chain ended.
+#[derive(Debug, PartialEq)]
+enum ChainResult {
+    Completed {
+        last: &'static str,
+    },
+    Stopped {
+        by: &'static str,
+    },
+    Rejected {
+        by: &'static str,
+        reason: String,
+    },
+    /// The department sent the patient to a department which does not come
+    /// after it.
+    Misrouted {
+        by: &'static str,
+        to: &'static str,
+    },
+}
+
+impl fmt::Display for ChainResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Completed { last } => write!(f, "Went through the chain up to '{last}'"),
+            Self::Stopped { by } => write!(f, "Sent home by '{by}'"),
+            Self::Rejected { by, reason } => write!(f, "Rejected by '{by}': {reason}"),
+            Self::Misrouted { by, to } => {
+                write!(f, "'{by}' sent the patient to '{to}', which is not ahead")
+            }
+        }
+    }
 }
 
 trait Department {
-    fn execute(&mut self, patient: &mut Patient) {
-        self.handle(patient);
+    fn execute(&mut self, patient: &mut Patient) -> ChainResult {
+        let name = self.name();
+        let outcome = self.handle(patient);
 
-        if let Some(next) = &mut self.next() {
-            next.execute(patient);
+        let mut next = self.next();
+        match outcome {
+            Outcome::Continue => (),
+            Outcome::Skip(departments) => {
+                for _ in 0..departments {
+                    match next {
+                        Some(department) => next = department.next(),
+                        None => break,
+                    }
+                }
+            }
+            Outcome::Branch(to) => {
+                while next
+                    .as_ref()
+                    .is_some_and(|department| department.name() != to)
+                {
+                    next = next.as_mut().expect("the slot is taken").next();
+                }
+                if next.is_none() {
+                    return ChainResult::Misrouted { by: name, to };
+                }
+            }
+            Outcome::Stop => return ChainResult::Stopped { by: name },
+            Outcome::Reject(reason) => return ChainResult::Rejected { by: name, reason },
+        }
+
+        match next {
+            Some(department) => department.execute(patient),
+            None => ChainResult::Completed { last: name },
         }
     }
 
-    fn handle(&mut self, patient: &mut Patient);
+    fn name(&self) -> &'static str;
+    fn handle(&mut self, patient: &mut Patient) -> Outcome;
     fn next(&mut self) -> &mut Option<Box<dyn Department>>;
 }
 
+/// How urgently the patient needs treatment, the most urgent first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Triage {
+    /// Treated before anything else, registered and billed afterwards.
+    Emergency,
+    Urgent,
+    #[default]
+    Standard,
+    NonUrgent,
+}
+
+impl fmt::Display for Triage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Emergency => "emergency",
+            Self::Urgent => "urgent",
+            Self::Standard => "standard",
+            Self::NonUrgent => "non-urgent",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Insurance {
+    #[default]
+    None,
+    Covered,
+    /// The premiums have not been paid.
+    Lapsed,
+}
+
 /// Request
 #[derive(Default)]
 struct Patient {
     name: String,
+    triage: Triage,
+    insurance: Insurance,
+    /// The doctor finds nothing to treat.
+    healthy: bool,
+    needs_lab_test: bool,
+    /// Diseases the patient has been screened for.
+    screened_for: Vec<&'static str>,
     registration_done: bool,
     doctor_check_up_done: bool,
+    lab_test_done: bool,
     medical_done: bool,
     payment_done: bool,
+    /// Services the patient got, in the order of the departments.
+    bill: Vec<&'static str>,
+    /// What the patient can still pay.
+    cash: Money,
+    paid: Money,
+}
+
+/// Amount of money in cents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Money(u32);
+
+impl Money {
+    /// The share, rounded down.
+    fn percent(self, percent: u32) -> Self {
+        let share = u64::from(self.0) * u64::from(percent) / 100;
+        Self(u32::try_from(share).unwrap_or(u32::MAX))
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+}
+
+impl std::ops::Sub for Money {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{}.{:02}", self.0 / 100, self.0 % 100))
+    }
+}
+
+impl std::str::FromStr for Money {
+    type Err = String;
+
+    /// Parses amounts like `60` or `60.50`.
+    fn from_str(text: &str) -> Result<Self, String> {
+        let invalid = || format!("'{text}' is not an amount");
+        let (units, cents) = text.split_once('.').unwrap_or((text, "0"));
+        let units: u32 = units.parse().map_err(|_| invalid())?;
+        let cents: u32 = match cents.len() {
+            1 => cents.parse::<u32>().map_err(|_| invalid())? * 10,
+            2 => cents.parse().map_err(|_| invalid())?,
+            _ => return Err(invalid()),
+        };
+
+        units
+            .checked_mul(100)
+            .and_then(|units| units.checked_add(cents))
+            .map(Self)
+            .ok_or_else(|| format!("'{text}' is too large an amount"))
+    }
+}
+
+#[derive(Debug)]
+enum PriceListError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for PriceListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Cannot read price list: {error}"),
+            Self::Parse { line, message } => write!(f, "price list line {line}: {message}"),
+        }
+    }
+}
+
+impl Error for PriceListError {}
+
+impl From<io::Error> for PriceListError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Prices of the services, the share of them insurance covers, and the
+/// discount on large invoices.
+#[derive(Clone, Debug, Default)]
+struct PriceList {
+    prices: HashMap<String, Money>,
+    // Percent of the price.
+    coverage: HashMap<String, u32>,
+    // Percent off what the patient pays, from this subtotal on.
+    discount: Option<(u32, Money)>,
+}
+
+impl PriceList {
+    /// Lines of `price <service> <amount>`, `cover <service> <percent>%` and
+    /// `discount <percent>% from <amount>`, `#` starts a comment.
+    fn parse(text: &str) -> Result<Self, PriceListError> {
+        let mut prices = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let fail = |message: String| PriceListError::Parse {
+                line: index + 1,
+                message,
+            };
+            let percent = |text: &str| -> Result<u32, PriceListError> {
+                text.strip_suffix('%')
+                    .and_then(|percent| percent.parse().ok())
+                    .filter(|percent| *percent <= 100)
+                    .ok_or_else(|| fail(format!("'{text}' is not a percentage")))
+            };
+
+            let fields: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            match fields.as_slice() {
+                [] => (),
+                ["price", service, amount] => {
+                    let amount = amount.parse().map_err(fail)?;
+                    prices.prices.insert(service.to_string(), amount);
+                }
+                ["cover", service, share] => {
+                    prices.coverage.insert(service.to_string(), percent(share)?);
+                }
+                ["discount", share, "from", amount] => {
+                    prices.discount = Some((percent(share)?, amount.parse().map_err(fail)?));
+                }
+                _ => return Err(fail(format!("cannot understand '{line}'"))),
+            }
+        }
+
+        Ok(prices)
+    }
+
+    fn load(path: &str) -> Result<Self, PriceListError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    fn invoice(&self, patient: &Patient) -> Result<Invoice, String> {
+        let items = patient
+            .bill
+            .iter()
+            .map(|service| match self.prices.get(*service) {
+                Some(price) => Ok((*service, *price)),
+                None => Err(format!("there is no price for '{service}'")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let subtotal = items
+            .iter()
+            .try_fold(Money(0), |total, (_, price)| total.checked_add(*price))
+            .ok_or_else(|| {
+                format!(
+                    "the invoice of {name} adds up to too large an amount",
+                    name = patient.name
+                )
+            })?;
+        let insurance = match patient.insurance {
+            Insurance::Covered => items.iter().fold(Money(0), |total, (service, price)| {
+                let share = self.coverage.get(*service).copied().unwrap_or(0);
+                total + price.percent(share)
+            }),
+            _ => Money(0),
+        };
+        let discount = match self.discount {
+            Some((share, from)) if subtotal >= from => (subtotal - insurance).percent(share),
+            _ => Money(0),
+        };
+
+        Ok(Invoice {
+            patient: patient.name.clone(),
+            items,
+            subtotal,
+            insurance,
+            discount,
+        })
+    }
+}
+
+struct Invoice {
+    patient: String,
+    items: Vec<(&'static str, Money)>,
+    subtotal: Money,
+    insurance: Money,
+    discount: Money,
+}
+
+impl Invoice {
+    /// What is left for the patient to pay.
+    fn total(&self) -> Money {
+        self.subtotal - self.insurance - self.discount
+    }
+}
+
+impl fmt::Display for Invoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Invoice for {patient}", patient = self.patient)?;
+        for (service, price) in &self.items {
+            writeln!(f, "  {service:<14}{price:>9}")?;
+        }
+        writeln!(f, "  {:<14}{:>9}", "subtotal", self.subtotal)?;
+        if self.insurance > Money(0) {
+            writeln!(
+                f,
+                "  {:<14}{:>9}",
+                "insurance",
+                format!("-{}", self.insurance)
+            )?;
+        }
+        if self.discount > Money(0) {
+            writeln!(
+                f,
+                "  {:<14}{:>9}",
+                "discount",
+                format!("-{}", self.discount)
+            )?;
+        }
+        writeln!(f, "  {:<14}{:>9}", "total", self.total())
+    }
 }
 
 /// Department/Cashier
-#[derive(Default)]
 struct Cashier {
+    prices: PriceList,
     next: Option<Box<dyn Department>>,
 }
 
+impl Cashier {
+    fn new(prices: PriceList) -> Self {
+        Self { prices, next: None }
+    }
+}
+
+impl Default for Cashier {
+    fn default() -> Self {
+        let prices =
+            PriceList::parse(include_str!("prices.txt")).expect("the built-in price list is valid");
+        Self::new(prices)
+    }
+}
+
 impl Department for Cashier {
-    fn handle(&mut self, patient: &mut Patient) {
+    fn name(&self) -> &'static str {
+        "cashier"
+    }
+
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
         if patient.payment_done {
             println!("Payment done")
+        } else if patient.insurance == Insurance::Lapsed {
+            return Outcome::Reject(format!(
+                "the insurance of {name} has lapsed",
+                name = patient.name
+            ));
         } else {
+            let invoice = match self.prices.invoice(patient) {
+                Ok(invoice) => invoice,
+                Err(reason) => return Outcome::Reject(reason),
+            };
+            print!("{invoice}");
+
+            let payment = (invoice.total() - patient.paid).min(patient.cash);
+            patient.cash = patient.cash - payment;
+            patient.paid = patient.paid + payment;
             println!(
-                "Cashier getting money from a patient: {name}",
+                "Cashier getting {payment} from a patient: {name}",
                 name = patient.name
             );
+
+            if patient.paid < invoice.total() {
+                return Outcome::Reject(format!(
+                    "{left} of {total} is not paid yet",
+                    left = invoice.total() - patient.paid,
+                    total = invoice.total()
+                ));
+            }
             patient.payment_done = true;
         }
+
+        Outcome::Continue
     }
 
     fn next(&mut self) -> &mut Option<Box<dyn Department>> {
@@ -70,27 +1147,74 @@ impl Department for Cashier {
     }
 }
 
-/// Department/Doctor
-#[derive(Default)]
-struct Doctor {
+/// Department/Screening
+struct Screening {
+    disease: &'static str,
     next: Option<Box<dyn Department>>,
 }
 
-impl Doctor {
-    fn new(next: impl Department + 'static) -> Self {
+impl Screening {
+    fn new(disease: &'static str) -> Self {
         Self {
-            next: into_next(next),
+            disease,
+            next: None,
+        }
+    }
+}
+
+impl Department for Screening {
+    fn name(&self) -> &'static str {
+        "screening"
+    }
+
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
+        if patient.screened_for.contains(&self.disease) {
+            println!(
+                "Screening for {disease} is already done",
+                disease = self.disease
+            )
+        } else {
+            println!(
+                "Screening a patient for {disease}: {name}",
+                disease = self.disease,
+                name = patient.name
+            );
+            patient.screened_for.push(self.disease);
+            patient.bill.push("screening");
         }
+
+        Outcome::Continue
     }
+
+    fn next(&mut self) -> &mut Option<Box<dyn Department>> {
+        &mut self.next
+    }
+}
+
+/// Department/Doctor
+#[derive(Default)]
+struct Doctor {
+    next: Option<Box<dyn Department>>,
 }
 
 impl Department for Doctor {
-    fn handle(&mut self, patient: &mut Patient) {
+    fn name(&self) -> &'static str {
+        "doctor"
+    }
+
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
         if patient.doctor_check_up_done {
             println!("Doctor check up done")
         } else {
             println!("Doctor checking a patient: {name}", name = patient.name);
             patient.doctor_check_up_done = true;
+            patient.bill.push("consultation");
+        }
+
+        if patient.needs_lab_test {
+            Outcome::Branch("lab")
+        } else {
+            Outcome::Continue
         }
     }
 
@@ -99,31 +1223,63 @@ impl Department for Doctor {
     }
 }
 
-/// Department/Medical
+/// Department/Lab
 #[derive(Default)]
-struct Medical {
+struct Lab {
     next: Option<Box<dyn Department>>,
 }
 
-impl Medical {
-    fn new(next: impl Department + 'static) -> Self {
-        Self {
-            next: into_next(next),
+impl Department for Lab {
+    fn name(&self) -> &'static str {
+        "lab"
+    }
+
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
+        if patient.lab_test_done {
+            println!("Lab test is already done")
+        } else {
+            println!("Lab testing a patient: {name}", name = patient.name);
+            patient.lab_test_done = true;
+            patient.bill.push("lab");
         }
+
+        Outcome::Continue
+    }
+
+    fn next(&mut self) -> &mut Option<Box<dyn Department>> {
+        &mut self.next
     }
 }
 
+/// Department/Medical
+#[derive(Default)]
+struct Medical {
+    next: Option<Box<dyn Department>>,
+}
+
 impl Department for Medical {
-    fn handle(&mut self, patient: &mut Patient) {
+    fn name(&self) -> &'static str {
+        "medical"
+    }
+
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
         if patient.medical_done {
             println!("Medicine is already given to a patient")
+        } else if patient.healthy {
+            println!(
+                "No medicine needed for a patient: {name}",
+                name = patient.name
+            )
         } else {
             println!(
                 "Medical giving medicine to a patient: {name}",
                 name = patient.name
             );
             patient.medical_done = true;
+            patient.bill.push("medicine");
         }
+
+        Outcome::Continue
     }
 
     fn next(&mut self) -> &mut Option<Box<dyn Department>> {
@@ -137,16 +1293,17 @@ struct Reception {
     next: Option<Box<dyn Department>>,
 }
 
-impl Reception {
-    fn new(next: impl Department + 'static) -> Self {
-        Self {
-            next: into_next(next),
-        }
+impl Department for Reception {
+    fn name(&self) -> &'static str {
+        "reception"
     }
-}
 
-impl Department for Reception {
-    fn handle(&mut self, patient: &mut Patient) {
+    fn handle(&mut self, patient: &mut Patient) -> Outcome {
+        if patient.payment_done {
+            println!("Patient {name} has already paid", name = patient.name);
+            return Outcome::Stop;
+        }
+
         if patient.registration_done {
             println!("Patient registration is already done")
         } else {
@@ -155,10 +1312,321 @@ impl Department for Reception {
                 name = patient.name
             );
             patient.registration_done = true;
+            patient.bill.push("registration");
         }
+
+        Outcome::Continue
     }
 
     fn next(&mut self) -> &mut Option<Box<dyn Department>> {
         &mut self.next
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(config: &str) -> Chain {
+        ChainBuilder::from_config(config, &DepartmentRegistry::default())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn healthy_patient() -> Patient {
+        Patient {
+            name: "Jane".to_string(),
+            healthy: true,
+            cash: Money(200 * 100),
+            ..Patient::default()
+        }
+    }
+
+    #[test]
+    fn healthy_patient_still_pays_without_a_medical_department() {
+        let mut chain = chain("reception\ndoctor\ncashier\n");
+        let mut patient = healthy_patient();
+
+        assert_eq!(
+            chain.execute(&mut patient),
+            ChainResult::Completed { last: "cashier" }
+        );
+        assert!(patient.payment_done);
+        assert_eq!(patient.bill, ["registration", "consultation"]);
+    }
+
+    #[test]
+    fn healthy_patient_visits_a_lab_inserted_after_the_doctor_but_gets_no_medicine() {
+        let mut chain = chain("reception\ndoctor\nmedical\ncashier\n");
+        chain.insert_after("doctor", Lab::default()).unwrap();
+        let mut patient = healthy_patient();
+
+        assert_eq!(
+            chain.execute(&mut patient),
+            ChainResult::Completed { last: "cashier" }
+        );
+        assert_eq!(patient.bill, ["registration", "consultation", "lab"]);
+        assert!(!patient.medical_done);
+    }
+
+    /// Sends every patient past the next department.
+    #[derive(Default)]
+    struct Shortcut {
+        next: Option<Box<dyn Department>>,
+    }
+
+    impl Department for Shortcut {
+        fn name(&self) -> &'static str {
+            "shortcut"
+        }
+
+        fn handle(&mut self, _patient: &mut Patient) -> Outcome {
+            Outcome::Skip(1)
+        }
+
+        fn next(&mut self) -> &mut Option<Box<dyn Department>> {
+            &mut self.next
+        }
+    }
+
+    #[test]
+    fn skipped_department_is_not_visited() {
+        let mut chain = ChainBuilder::new()
+            .then(Shortcut::default())
+            .then(Reception::default())
+            .then(Doctor::default())
+            .build()
+            .unwrap();
+        let mut patient = healthy_patient();
+
+        assert_eq!(
+            chain.execute(&mut patient),
+            ChainResult::Completed { last: "doctor" }
+        );
+        assert!(!patient.registration_done);
+        assert!(patient.doctor_check_up_done);
+    }
+
+    #[test]
+    fn departments_are_inserted_at_the_head_and_the_tail() {
+        let mut chain = chain("doctor\nmedical\n");
+
+        chain.insert_before("doctor", Reception::default()).unwrap();
+        chain.insert_after("medical", Cashier::default()).unwrap();
+        chain.insert_after("doctor", Lab::default()).unwrap();
+
+        assert_eq!(
+            chain.names(),
+            ["reception", "doctor", "lab", "medical", "cashier"]
+        );
+    }
+
+    #[test]
+    fn department_is_replaced_by_one_of_the_same_name() {
+        let mut chain = chain("reception\nscreening\ndoctor\n");
+
+        chain.replace("screening", Screening::new("flu")).unwrap();
+        let mut patient = healthy_patient();
+        chain.execute(&mut patient);
+
+        assert_eq!(chain.names(), ["reception", "screening", "doctor"]);
+        assert_eq!(patient.screened_for, ["flu"]);
+    }
+
+    #[test]
+    fn departments_are_removed_but_not_the_last_one() {
+        let mut chain = chain("reception\ndoctor\n");
+
+        assert_eq!(chain.remove("reception").unwrap().name(), "reception");
+        assert!(matches!(chain.remove("doctor"), Err(ChainError::Empty)));
+        assert_eq!(chain.names(), ["doctor"]);
+    }
+
+    #[test]
+    fn departments_not_in_the_chain_cannot_be_changed() {
+        let mut chain = chain("reception\ndoctor\n");
+
+        assert!(matches!(
+            chain.insert_before("lab", Medical::default()),
+            Err(ChainError::NotInChain(name)) if name == "lab"
+        ));
+        assert!(matches!(
+            chain.insert_after("lab", Medical::default()),
+            Err(ChainError::NotInChain(_))
+        ));
+        assert!(matches!(
+            chain.replace("lab", Medical::default()),
+            Err(ChainError::NotInChain(_))
+        ));
+        assert!(matches!(
+            chain.remove("lab"),
+            Err(ChainError::NotInChain(_))
+        ));
+        assert_eq!(chain.names(), ["reception", "doctor"]);
+    }
+
+    #[test]
+    fn department_cannot_be_in_the_chain_twice() {
+        let mut chain = chain("reception\ndoctor\nmedical\n");
+
+        assert!(matches!(
+            chain.insert_after("medical", Doctor::default()),
+            Err(ChainError::Cycle("doctor"))
+        ));
+        assert!(matches!(
+            chain.insert_before("reception", Doctor::default()),
+            Err(ChainError::Cycle("doctor"))
+        ));
+        assert!(matches!(
+            chain.replace("medical", Doctor::default()),
+            Err(ChainError::Cycle("doctor"))
+        ));
+        assert_eq!(chain.names(), ["reception", "doctor", "medical"]);
+    }
+
+    #[test]
+    fn workflow_with_a_loop_is_refused_with_the_looping_path() {
+        let workflow = WorkflowBuilder::new()
+            .department(Reception::default(), &["doctor"])
+            .department(Doctor::default(), &["lab", "cashier"])
+            .department(Lab::default(), &["doctor"])
+            .department(Cashier::default(), &[])
+            .build();
+
+        assert!(matches!(
+            workflow.err(),
+            Some(ChainError::Loop(path)) if path == ["doctor", "lab", "doctor"]
+        ));
+    }
+
+    #[test]
+    fn workflow_leading_to_a_missing_department_is_refused() {
+        let workflow = WorkflowBuilder::new()
+            .department(Reception::default(), &["doctor"])
+            .department(Doctor::default(), &["surgeon"])
+            .build();
+
+        assert!(matches!(
+            workflow.err(),
+            Some(ChainError::UnknownSuccessor {
+                from: "doctor",
+                to: "surgeon"
+            })
+        ));
+    }
+
+    #[test]
+    fn workflow_with_a_department_twice_is_refused() {
+        let workflow = WorkflowBuilder::new()
+            .department(Reception::default(), &["doctor"])
+            .department(Doctor::default(), &[])
+            .department(Doctor::default(), &[])
+            .build();
+
+        assert!(matches!(workflow.err(), Some(ChainError::Cycle("doctor"))));
+    }
+
+    #[test]
+    fn workflow_whose_branches_join_again_is_accepted() {
+        let mut workflow = WorkflowBuilder::new()
+            .department(Reception::default(), &["doctor", "lab"])
+            .department(Doctor::default(), &["medical"])
+            .department(Lab::default(), &["medical"])
+            .department(Medical::default(), &["cashier"])
+            .department(Cashier::default(), &[])
+            .build()
+            .unwrap();
+        let mut patient = Patient {
+            cash: Money(200 * 100),
+            ..Patient::default()
+        };
+
+        assert_eq!(
+            workflow.execute(&mut patient),
+            ChainResult::Completed { last: "cashier" }
+        );
+        assert_eq!(patient.bill, ["registration", "consultation", "medicine"]);
+    }
+
+    #[test]
+    fn invoice_too_large_to_add_up_is_refused() {
+        let prices =
+            PriceList::parse("price consultation 30000000\nprice medicine 30000000\n").unwrap();
+        let patient = Patient {
+            name: "Jim".to_string(),
+            bill: vec!["consultation", "medicine"],
+            ..Patient::default()
+        };
+
+        assert_eq!(
+            prices.invoice(&patient).err(),
+            Some("the invoice of Jim adds up to too large an amount".to_string())
+        );
+    }
+
+    #[test]
+    fn emergency_is_treated_first_and_the_chain_keeps_its_order() {
+        let mut chain = chain("reception\ndoctor\nmedical\ncashier\n");
+        let mut patient = Patient {
+            triage: Triage::Emergency,
+            cash: Money(200 * 100),
+            ..Patient::default()
+        };
+
+        assert_eq!(
+            chain.execute_deferring(&mut patient, &DEFERRED_IN_EMERGENCY),
+            ChainResult::Completed { last: "cashier" }
+        );
+        assert_eq!(patient.bill, ["consultation", "medicine", "registration"]);
+        assert!(patient.payment_done);
+        assert_eq!(chain.names(), ["reception", "doctor", "medical", "cashier"]);
+    }
+
+    #[test]
+    fn deferred_departments_are_not_visited_once_the_chain_has_ended() {
+        let mut chain = chain("reception\ndoctor\nmedical\ncashier\n");
+        // There is no lab to send the patient to.
+        let mut patient = Patient {
+            triage: Triage::Emergency,
+            needs_lab_test: true,
+            cash: Money(200 * 100),
+            ..Patient::default()
+        };
+
+        assert_eq!(
+            chain.execute_deferring(&mut patient, &DEFERRED_IN_EMERGENCY),
+            ChainResult::Misrouted {
+                by: "doctor",
+                to: "lab"
+            }
+        );
+        assert!(!patient.registration_done);
+        assert!(!patient.payment_done);
+        assert_eq!(chain.names(), ["reception", "doctor", "medical", "cashier"]);
+    }
+
+    #[test]
+    fn patients_of_the_same_triage_are_called_in_arrival_order() {
+        let mut waiting_room = WaitingRoom::default();
+        for (name, triage) in [
+            ("Tom", Triage::Standard),
+            ("Eve", Triage::NonUrgent),
+            ("Liz", Triage::Urgent),
+            ("Ray", Triage::Standard),
+            ("Max", Triage::Urgent),
+        ] {
+            waiting_room.arrive(Patient {
+                name: name.to_string(),
+                triage,
+                ..Patient::default()
+            });
+        }
+
+        let called: Vec<String> = std::iter::from_fn(|| waiting_room.call())
+            .map(|patient| patient.name)
+            .collect();
+
+        assert_eq!(called, ["Liz", "Max", "Tom", "Ray", "Eve"]);
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::station::{EventLog, StationError, StationEvent, TrainStation};
+use crate::train::Train;
+
+type Reply<T> = Sender<Result<T, StationError>>;
+
+enum Request {
+    /// Answered with the admission grant, which may come much later if the
+    /// train has to wait.
+    Arrival {
+        train: Box<dyn Train + Send>,
+        grant: Reply<usize>,
+    },
+    Departure {
+        train: String,
+        done: Reply<()>,
+    },
+}
+
+/// Station running on its own thread. Trains talk to it through messages, so
+/// they can be driven from any thread, and the station handles one message at
+/// a time with the same admission rules as `TrainStation`.
+pub struct StationActor {
+    handle: StationHandle,
+    thread: JoinHandle<()>,
+}
+
+/// Sending side of a `StationActor`, cheap to clone for every train thread.
+#[derive(Clone)]
+pub struct StationHandle {
+    requests: Sender<Request>,
+}
+
+impl StationActor {
+    /// The station is built on the actor thread, since it is not `Send`.
+    pub fn spawn(build: impl FnOnce() -> TrainStation + Send + 'static) -> Self {
+        let (requests, inbox) = mpsc::channel();
+        let thread = thread::spawn(move || run(build(), inbox));
+
+        Self {
+            handle: StationHandle { requests },
+            thread,
+        }
+    }
+
+    pub fn handle(&self) -> StationHandle {
+        self.handle.clone()
+    }
+
+    /// Waits until every handle is dropped and the station has stopped.
+    pub fn join(self) {
+        drop(self.handle);
+        // A panic on the station thread is a bug, pass it on.
+        if let Err(panic) = self.thread.join() {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
+
+impl StationHandle {
+    /// Blocks until the train is admitted and returns its platform.
+    pub fn arrive(&self, train: Box<dyn Train + Send>) -> Result<usize, StationError> {
+        let (grant, granted) = mpsc::channel();
+        self.send(Request::Arrival { train, grant })?;
+
+        granted.recv().map_err(|_| stopped())?
+    }
+
+    pub fn depart(&self, train: &str) -> Result<(), StationError> {
+        let (done, finished) = mpsc::channel();
+        self.send(Request::Departure {
+            train: train.to_string(),
+            done,
+        })?;
+
+        finished.recv().map_err(|_| stopped())?
+    }
+
+    fn send(&self, request: Request) -> Result<(), StationError> {
+        self.requests.send(request).map_err(|_| stopped())
+    }
+}
+
+fn stopped() -> StationError {
+    StationError::Inconsistency("the station thread has stopped".to_string())
+}
+
+fn run(mut station: TrainStation, inbox: Receiver<Request>) {
+    let log = EventLog::default();
+    station.subscribe(log.subscriber());
+
+    // Trains which have not got their platform yet.
+    let mut grants: HashMap<String, Reply<usize>> = HashMap::new();
+
+    for request in inbox {
+        match request {
+            Request::Arrival { train, grant } => {
+                let name = train.name().to_string();
+                match station.accept_boxed(train) {
+                    Ok(_) => {
+                        grants.insert(name, grant);
+                    }
+                    // The train may have given up waiting, nothing to do then.
+                    Err(error) => {
+                        let _ = grant.send(Err(error));
+                    }
+                }
+            }
+            Request::Departure { train, done } => {
+                let departed = station.depart(&train);
+                // A train which leaves while waiting never gets its platform.
+                if departed.is_ok() {
+                    if let Some(grant) = grants.remove(&train) {
+                        let _ = grant.send(Err(StationError::Withdrawn(train)));
+                    }
+                }
+                let _ = done.send(departed);
+            }
+        }
+
+        for event in log.take() {
+            if let StationEvent::Admitted { train, platform } = event {
+                if let Some(grant) = grants.remove(&train) {
+                    let _ = grant.send(Ok(platform));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::platform::{FirstFree, LeastRecentlyUsed};
+    use crate::train::{FreightTrain, PassengerTrain};
+
+    #[test]
+    fn trains_on_many_threads_never_share_a_platform() {
+        const PLATFORMS: usize = 3;
+        const TRAINS: usize = 12;
+        const VISITS: usize = 25;
+
+        let actor = StationActor::spawn(|| TrainStation::new(PLATFORMS, LeastRecentlyUsed));
+        // What the trains themselves see on the platforms.
+        let occupied = Arc::new(Mutex::new(vec![None::<String>; PLATFORMS]));
+
+        let threads: Vec<_> = (0..TRAINS)
+            .map(|index| {
+                let station = actor.handle();
+                let occupied = Arc::clone(&occupied);
+
+                thread::spawn(move || {
+                    let name = format!("train{index}");
+                    for _ in 0..VISITS {
+                        let train: Box<dyn Train + Send> = match index % 3 {
+                            0 => Box::new(FreightTrain::new(name.clone())),
+                            1 => Box::new(PassengerTrain::new(name.clone())),
+                            _ => Box::new(PassengerTrain::express(name.clone())),
+                        };
+                        let platform = station.arrive(train).unwrap();
+
+                        let other = occupied.lock().unwrap()[platform].replace(name.clone());
+                        assert_eq!(other, None, "platform {platform} given to '{name}' twice");
+                        thread::sleep(Duration::from_micros(100));
+                        occupied.lock().unwrap()[platform] = None;
+
+                        station.depart(&name).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        actor.join();
+    }
+
+    #[test]
+    fn waiting_train_which_departs_is_told_it_gets_no_platform() {
+        let actor = StationActor::spawn(|| TrainStation::new(1, FirstFree));
+        let station = actor.handle();
+        station
+            .arrive(Box::new(PassengerTrain::new("RB 1".to_string())))
+            .unwrap();
+
+        let waiting = {
+            let station = actor.handle();
+            thread::spawn(move || station.arrive(Box::new(FreightTrain::new("CT 2".to_string()))))
+        };
+        // The arrival may not have reached the station yet.
+        while station.depart("CT 2").is_err() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(
+            waiting.join().unwrap(),
+            Err(StationError::Withdrawn("CT 2".to_string()))
+        );
+        station.depart("RB 1").unwrap();
+        drop(station);
+        actor.join();
+    }
+}
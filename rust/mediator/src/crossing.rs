@@ -0,0 +1,128 @@
+use crate::framework::{Colleague, ColleagueId, Envelope, Hub, Message};
+
+/// Level crossing on the line: the signal box only clears the signal once the
+/// barriers are down, and raises them again when the train has passed.
+pub enum CrossingMessage {
+    Approaching(String),
+    Passed(String),
+    Lower,
+    Raise,
+}
+
+impl Message for CrossingMessage {
+    /// Whether the crossing is closed to road traffic.
+    type Reply = bool;
+}
+
+#[derive(Default)]
+pub struct Barriers {
+    down: bool,
+}
+
+impl Colleague<CrossingMessage> for Barriers {
+    fn receive(
+        &mut self,
+        envelope: Envelope<CrossingMessage>,
+        _hub: &mut Hub<CrossingMessage>,
+    ) -> bool {
+        match envelope.message {
+            CrossingMessage::Lower if !self.down => {
+                println!("Barriers: Lowering");
+                self.down = true;
+            }
+            CrossingMessage::Raise if self.down => {
+                println!("Barriers: Raising");
+                self.down = false;
+            }
+            _ => {}
+        }
+
+        self.down
+    }
+}
+
+pub struct SignalBox {
+    barriers: ColleagueId,
+    // As far as the signal box knows.
+    closed: bool,
+}
+
+impl SignalBox {
+    pub fn new(barriers: ColleagueId) -> Self {
+        Self {
+            barriers,
+            closed: false,
+        }
+    }
+}
+
+impl Colleague<CrossingMessage> for SignalBox {
+    fn receive(
+        &mut self,
+        envelope: Envelope<CrossingMessage>,
+        hub: &mut Hub<CrossingMessage>,
+    ) -> bool {
+        let me = envelope.to;
+        match envelope.message {
+            CrossingMessage::Approaching(train) => {
+                self.closed = hub
+                    .request(me, self.barriers, CrossingMessage::Lower)
+                    .unwrap_or(false);
+                let signal = if self.closed { "clear" } else { "at danger" };
+                println!("Signal box: Signal for '{train}' is {signal}");
+                self.closed
+            }
+            CrossingMessage::Passed(train) => {
+                println!(
+                    "Signal box: '{train}' has passed sensor {sensor}",
+                    sensor = envelope.from
+                );
+                // Nobody needs to wait for the barriers to go up, the crossing
+                // stays closed until they get the message.
+                let closed = self.closed;
+                if hub.post(me, self.barriers, CrossingMessage::Raise).is_ok() {
+                    self.closed = false;
+                }
+                closed
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Track circuit reporting trains to the signal box.
+pub struct TrackSensor;
+
+impl Colleague<CrossingMessage> for TrackSensor {
+    fn receive(
+        &mut self,
+        _envelope: Envelope<CrossingMessage>,
+        _hub: &mut Hub<CrossingMessage>,
+    ) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossing_stays_closed_until_the_barriers_are_raised() {
+        let mut hub = Hub::default();
+        let barriers = hub.register(Barriers::default());
+        let signal_box = hub.register(SignalBox::new(barriers));
+        let sensor = hub.register(TrackSensor);
+
+        let approaching = CrossingMessage::Approaching("IC 1".to_string());
+        assert_eq!(hub.request(sensor, signal_box, approaching), Ok(true));
+
+        let passed = CrossingMessage::Passed("IC 1".to_string());
+        assert_eq!(hub.request(sensor, signal_box, passed), Ok(true));
+        assert_eq!(hub.run(), Ok(1));
+        assert_eq!(
+            hub.request(signal_box, barriers, CrossingMessage::Raise),
+            Ok(false)
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use cursive::{
+    event::Key,
+    view::{Nameable, Resizable, ScrollStrategy},
+    views::{Dialog, LinearLayout, Panel, ScrollView, TextView},
+    Cursive,
+};
+
+use crate::platform::{LeastRecentlyUsed, Platform};
+use crate::station::{EventLog, TrainStation};
+use crate::train::{FreightTrain, PassengerTrain, Train, TrainKind};
+
+const HELP: &str = "p/e/f: passenger, express or freight train arrives\n\
+                    0-9: train on the platform departs\n\
+                    w: first waiting train leaves\n\
+                    Esc: quit";
+
+/// Live view of a station. It only renders what the station exposes and turns
+/// keys into `accept` and `depart` calls, the station itself knows nothing
+/// about it.
+struct Dashboard {
+    station: TrainStation,
+    log: EventLog,
+    arrivals: usize,
+}
+
+pub fn run() {
+    let mut station = TrainStation::with_platforms(
+        vec![
+            Platform::new(400, &[TrainKind::Passenger, TrainKind::Freight]),
+            Platform::new(200, &[TrainKind::Passenger]),
+            Platform::new(250, &[TrainKind::Passenger]),
+        ],
+        LeastRecentlyUsed,
+    );
+    let log = EventLog::default();
+    station.subscribe(log.subscriber());
+
+    let mut app = cursive::default();
+    app.set_user_data(Dashboard {
+        station,
+        log,
+        arrivals: 0,
+    });
+
+    let state = LinearLayout::horizontal()
+        .child(Panel::new(TextView::new("").with_name("Platforms")).title("Platforms"))
+        .child(Panel::new(TextView::new("").with_name("Queue")).title("Waiting"));
+    let events = ScrollView::new(TextView::new("").with_name("Events"))
+        .scroll_strategy(ScrollStrategy::StickToBottom);
+
+    app.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(state)
+                .child(Panel::new(events).title("Events").min_height(10))
+                .child(TextView::new(HELP)),
+        )
+        .title("Train Station"),
+    );
+
+    for key in ['p', 'e', 'f'] {
+        app.add_global_callback(key, move |s| arrive(s, key));
+    }
+    for key in '0'..='9' {
+        let platform = key as usize - '0' as usize;
+        app.add_global_callback(key, move |s| depart_from(s, platform));
+    }
+    app.add_global_callback('w', withdraw);
+    app.add_global_callback(Key::Esc, |s| s.quit());
+
+    render(&mut app);
+    app.run();
+}
+
+fn arrive(s: &mut Cursive, key: char) {
+    s.with_user_data(|dashboard: &mut Dashboard| {
+        dashboard.arrivals += 1;
+        let number = dashboard.arrivals;
+        let train: Box<dyn Train> = match key {
+            'p' => Box::new(PassengerTrain::new(format!("RB {number}"))),
+            'e' => Box::new(PassengerTrain::express(format!("IC {number}"))),
+            _ => Box::new(FreightTrain::new(format!("CT {number}")).with_cars(10)),
+        };
+        // Refusals show up in the event log.
+        let _ = dashboard.station.accept_boxed(train);
+    });
+    render(s);
+}
+
+fn depart_from(s: &mut Cursive, platform: usize) {
+    s.with_user_data(|dashboard: &mut Dashboard| {
+        let occupant = dashboard
+            .station
+            .platforms()
+            .get(platform)
+            .and_then(|platform| platform.train.clone());
+        if let Some(train) = occupant {
+            let _ = dashboard.station.depart(train);
+        }
+    });
+    render(s);
+}
+
+fn withdraw(s: &mut Cursive) {
+    s.with_user_data(|dashboard: &mut Dashboard| {
+        let first = dashboard.station.waiting().next().map(str::to_string);
+        if let Some(train) = first {
+            let _ = dashboard.station.depart(train);
+        }
+    });
+    render(s);
+}
+
+fn render(s: &mut Cursive) {
+    let Some(dashboard) = s.user_data::<Dashboard>() else {
+        return;
+    };
+
+    let platforms: Vec<String> = dashboard
+        .station
+        .platforms()
+        .iter()
+        .enumerate()
+        .map(|(index, platform)| {
+            let occupant = platform.train.as_deref().unwrap_or("-");
+            format!("{index} ({length} m): {occupant}", length = platform.length)
+        })
+        .collect();
+    let waiting: Vec<&str> = dashboard.station.waiting().collect();
+    let events: Vec<String> = dashboard
+        .log
+        .take()
+        .iter()
+        .map(|event| format!("{event}\n"))
+        .collect();
+
+    let platforms = platforms.join("\n");
+    let waiting = if waiting.is_empty() {
+        "-".to_string()
+    } else {
+        waiting.join("\n")
+    };
+
+    s.call_on_name("Platforms", |view: &mut TextView| {
+        view.set_content(platforms)
+    });
+    s.call_on_name("Queue", |view: &mut TextView| view.set_content(waiting));
+    s.call_on_name("Events", |view: &mut TextView| {
+        for event in events {
+            view.append(event);
+        }
+    });
+}
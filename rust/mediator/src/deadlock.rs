@@ -0,0 +1,79 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::network::BlockId;
+
+/// Something a train can be blocked on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// The platforms of a station.
+    Platforms(String),
+    Block(BlockId),
+}
+
+/// Wait-for graph of trains and resources. A train waits for at most one
+/// resource, and the resource becomes available as soon as any of the trains
+/// holding it moves on.
+#[derive(Default)]
+pub struct WaitForGraph {
+    waits: HashMap<String, Resource>,
+    holders: HashMap<Resource, Vec<String>>,
+}
+
+impl WaitForGraph {
+    pub fn wait(&mut self, train: &str, resource: Resource) {
+        self.waits.insert(train.to_string(), resource);
+    }
+
+    pub fn hold(&mut self, resource: Resource, train: &str) {
+        self.holders
+            .entry(resource)
+            .or_default()
+            .push(train.to_string());
+    }
+
+    /// Trains which can never move on, because everything they wait for is
+    /// held by trains which are stuck themselves. Sorted by name.
+    pub fn deadlocked(&self) -> Vec<String> {
+        // Trains which are not waiting can always move on, and so can every
+        // train waiting for a resource one of them holds.
+        let mut moving: HashSet<&str> = self
+            .holders
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .filter(|train| !self.waits.contains_key(*train))
+            .collect();
+
+        loop {
+            let unblocked: Vec<&str> = self
+                .waits
+                .iter()
+                .filter(|(train, _)| !moving.contains(train.as_str()))
+                .filter(|(_, resource)| {
+                    self.holders.get(*resource).is_none_or(|holders| {
+                        holders.is_empty()
+                            || holders
+                                .iter()
+                                .any(|holder| moving.contains(holder.as_str()))
+                    })
+                })
+                .map(|(train, _)| train.as_str())
+                .collect();
+
+            if unblocked.is_empty() {
+                break;
+            }
+            moving.extend(unblocked);
+        }
+
+        let mut deadlocked: Vec<String> = self
+            .waits
+            .keys()
+            .filter(|train| !moving.contains(train.as_str()))
+            .cloned()
+            .collect();
+        deadlocked.sort();
+
+        deadlocked
+    }
+}
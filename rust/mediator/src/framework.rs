@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Something colleagues send each other, together with the reply it gets when
+/// it is a request.
+pub trait Message {
+    type Reply;
+}
+
+/// Handles messages of type `M` on behalf of the colleagues, so they never
+/// talk to each other directly.
+pub trait Mediator<M: Message> {
+    /// Handles the message right away and answers it.
+    fn request(&mut self, message: M) -> M::Reply;
+
+    /// Handles the message without anybody waiting for the answer.
+    fn notify(&mut self, message: M) {
+        self.request(message);
+    }
+}
+
+/// Handle of a colleague registered with a `Hub`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ColleagueId(usize);
+
+impl fmt::Display for ColleagueId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+pub struct Envelope<M> {
+    pub from: ColleagueId,
+    pub to: ColleagueId,
+    pub message: M,
+}
+
+pub trait Colleague<M: Message> {
+    /// Handles a message addressed to this colleague. The hub is there to
+    /// talk to the other colleagues meanwhile.
+    fn receive(&mut self, envelope: Envelope<M>, hub: &mut Hub<M>) -> M::Reply;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HubError {
+    UnknownColleague(ColleagueId),
+    /// The colleague is still handling an earlier message, e.g. it asked
+    /// itself something.
+    Busy(ColleagueId),
+}
+
+impl fmt::Display for HubError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownColleague(id) => write!(f, "No colleague {id}"),
+            Self::Busy(id) => write!(f, "Colleague {id} is busy"),
+        }
+    }
+}
+
+impl std::error::Error for HubError {}
+
+/// Mediator which routes messages between colleagues registered by handle.
+/// Requests are delivered at once, posted messages when the hub gets to them.
+pub struct Hub<M: Message> {
+    // A colleague is taken out of its slot while it handles a message.
+    colleagues: Vec<Slot<M>>,
+    mailbox: VecDeque<Envelope<M>>,
+}
+
+enum Slot<M: Message> {
+    Idle(Box<dyn Colleague<M>>),
+    Busy,
+    Gone,
+}
+
+impl<M: Message> Default for Hub<M> {
+    fn default() -> Self {
+        Self {
+            colleagues: Vec::new(),
+            mailbox: VecDeque::new(),
+        }
+    }
+}
+
+impl<M: Message> Hub<M> {
+    pub fn register(&mut self, colleague: impl Colleague<M> + 'static) -> ColleagueId {
+        self.colleagues.push(Slot::Idle(Box::new(colleague)));
+        ColleagueId(self.colleagues.len() - 1)
+    }
+
+    /// Handles are never reused, messages to a removed colleague fail.
+    pub fn unregister(&mut self, id: ColleagueId) -> Result<Box<dyn Colleague<M>>, HubError> {
+        let slot = self
+            .colleagues
+            .get_mut(id.0)
+            .ok_or(HubError::UnknownColleague(id))?;
+        match std::mem::replace(slot, Slot::Gone) {
+            Slot::Idle(colleague) => Ok(colleague),
+            Slot::Busy => {
+                *slot = Slot::Busy;
+                Err(HubError::Busy(id))
+            }
+            Slot::Gone => Err(HubError::UnknownColleague(id)),
+        }
+    }
+
+    /// Delivers the message right away and returns the answer.
+    pub fn request(
+        &mut self,
+        from: ColleagueId,
+        to: ColleagueId,
+        message: M,
+    ) -> Result<M::Reply, HubError> {
+        self.check(from)?;
+        self.deliver(Envelope { from, to, message })
+    }
+
+    /// Leaves the message for `run`, nobody waits for the answer.
+    pub fn post(&mut self, from: ColleagueId, to: ColleagueId, message: M) -> Result<(), HubError> {
+        self.check(from)?;
+        self.check(to)?;
+        self.mailbox.push_back(Envelope { from, to, message });
+
+        Ok(())
+    }
+
+    fn check(&self, id: ColleagueId) -> Result<(), HubError> {
+        match self.colleagues.get(id.0) {
+            None | Some(Slot::Gone) => Err(HubError::UnknownColleague(id)),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Delivers posted messages, also those posted meanwhile, until the
+    /// mailbox is empty. Returns how many were delivered.
+    pub fn run(&mut self) -> Result<usize, HubError> {
+        let mut delivered = 0;
+        while let Some(envelope) = self.mailbox.pop_front() {
+            self.deliver(envelope)?;
+            delivered += 1;
+        }
+
+        Ok(delivered)
+    }
+
+    fn deliver(&mut self, envelope: Envelope<M>) -> Result<M::Reply, HubError> {
+        let to = envelope.to;
+        let slot = self
+            .colleagues
+            .get_mut(to.0)
+            .ok_or(HubError::UnknownColleague(to))?;
+        let mut colleague = match std::mem::replace(slot, Slot::Busy) {
+            Slot::Idle(colleague) => colleague,
+            Slot::Busy => return Err(HubError::Busy(to)),
+            Slot::Gone => {
+                *slot = Slot::Gone;
+                return Err(HubError::UnknownColleague(to));
+            }
+        };
+
+        let reply = colleague.receive(envelope, self);
+        self.colleagues[to.0] = Slot::Idle(colleague);
+
+        Ok(reply)
+    }
+}
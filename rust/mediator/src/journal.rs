@@ -0,0 +1,344 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::platform::PlatformAssignment;
+use crate::station::{
+    EventLog, StationError, StationEvent, StationSnapshot, TrainId, TrainRef, TrainStation,
+};
+use crate::time::Time;
+use crate::train::{Train, TrainSpec};
+
+/// Something done to a station from outside.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Input {
+    Accept(TrainSpec),
+    Depart(TrainRef),
+    Cancel(TrainRef),
+    AdvanceTo(Time),
+}
+
+/// An input and everything the station answered to it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub input: Input,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub events: Vec<String>,
+}
+
+/// Session of a station: where it started and every input since, so it can be
+/// replayed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    pub start: StationSnapshot,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    /// The station the journal starts from cannot be restored.
+    Station(StationError),
+    /// Entry `entry` went differently this time.
+    Diverged {
+        entry: usize,
+        expected: Box<Entry>,
+        actual: Box<Entry>,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Cannot access journal: {error}"),
+            Self::Parse(error) => write!(f, "Broken journal: {error}"),
+            Self::Station(error) => write!(f, "Cannot restore journal start: {error}"),
+            Self::Diverged {
+                entry,
+                expected,
+                actual,
+            } => {
+                write!(f, "Entry {entry} diverged")?;
+                if expected.error != actual.error {
+                    write!(
+                        f,
+                        ", error {expected:?} became {actual:?}",
+                        expected = expected.error,
+                        actual = actual.error
+                    )?;
+                }
+                let position = expected
+                    .events
+                    .iter()
+                    .zip(&actual.events)
+                    .position(|(expected, actual)| expected != actual)
+                    .unwrap_or(expected.events.len().min(actual.events.len()));
+                if position < expected.events.len().max(actual.events.len()) {
+                    let event = |events: &[String]| {
+                        events
+                            .get(position)
+                            .map_or("nothing".to_string(), |event| format!("\"{event}\""))
+                    };
+                    write!(
+                        f,
+                        ", event {position}: expected {expected}, got {actual}",
+                        expected = event(&expected.events),
+                        actual = event(&actual.events)
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl Journal {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a journal is always valid JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        Ok(fs::write(path, self.to_json() + "\n")?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Ok(Self::from_json(&fs::read_to_string(path)?)?)
+    }
+
+    /// Runs the inputs again on the starting station and checks that every
+    /// one of them gives the same error and the same events, character by
+    /// character. Returns the station as it is after the last input.
+    pub fn replay(
+        &self,
+        assignment: impl PlatformAssignment + 'static,
+    ) -> Result<TrainStation, ReplayError> {
+        let station =
+            TrainStation::restore(self.start.clone(), assignment).map_err(ReplayError::Station)?;
+        let mut recorder = Recorder::new(station);
+
+        for (index, expected) in self.entries.iter().enumerate() {
+            let actual = recorder.apply(expected.input.clone());
+            if actual != *expected {
+                return Err(ReplayError::Diverged {
+                    entry: index,
+                    expected: Box::new(expected.clone()),
+                    actual: Box::new(actual),
+                });
+            }
+        }
+
+        Ok(recorder.station)
+    }
+}
+
+/// Station which writes every input and what came of it into a journal.
+/// Trains already on the station keep their handles on replay.
+pub struct Recorder {
+    station: TrainStation,
+    log: EventLog,
+    journal: Journal,
+}
+
+impl Recorder {
+    pub fn new(mut station: TrainStation) -> Self {
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+        let journal = Journal {
+            start: station.snapshot(),
+            entries: Vec::new(),
+        };
+
+        Self {
+            station,
+            log,
+            journal,
+        }
+    }
+
+    pub fn station(&self) -> &TrainStation {
+        &self.station
+    }
+
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    pub fn accept(&mut self, train: impl Train + 'static) -> Result<TrainId, StationError> {
+        self.accept_boxed(Box::new(train))
+    }
+
+    pub fn accept_boxed(&mut self, train: Box<dyn Train>) -> Result<TrainId, StationError> {
+        let input = Input::Accept(TrainSpec::of(train.as_ref()));
+        self.record(input, |station| station.accept_boxed(train))
+    }
+
+    pub fn depart(&mut self, train: impl Into<TrainRef>) -> Result<(), StationError> {
+        let train = train.into();
+        self.record(Input::Depart(train.clone()), |station| {
+            station.depart(train)
+        })
+    }
+
+    pub fn cancel(&mut self, train: impl Into<TrainRef>) -> Result<(), StationError> {
+        let train = train.into();
+        self.record(Input::Cancel(train.clone()), |station| {
+            station.cancel(train)
+        })
+    }
+
+    pub fn advance_to(&mut self, time: Time) {
+        self.record(Input::AdvanceTo(time), |station| {
+            station.advance_to(time);
+            Ok(())
+        })
+        .expect("advancing the clock cannot fail");
+    }
+
+    fn apply(&mut self, input: Input) -> Entry {
+        // The outcome is in the entry.
+        let _ = match input {
+            Input::Accept(spec) => self.accept_boxed(spec.build()).map(drop),
+            Input::Depart(train) => self.depart(train),
+            Input::Cancel(train) => self.cancel(train),
+            Input::AdvanceTo(time) => {
+                self.advance_to(time);
+                Ok(())
+            }
+        };
+
+        self.journal
+            .entries
+            .last()
+            .cloned()
+            .expect("the input was just recorded")
+    }
+
+    fn record<T>(
+        &mut self,
+        input: Input,
+        run: impl FnOnce(&mut TrainStation) -> Result<T, StationError>,
+    ) -> Result<T, StationError> {
+        let result = run(&mut self.station);
+        let events = self.log.take();
+        self.journal.entries.push(Entry {
+            input,
+            error: result.as_ref().err().map(StationError::to_string),
+            events: events.iter().map(StationEvent::to_string).collect(),
+        });
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{FirstFree, LeastRecentlyUsed};
+    use crate::train::{FreightTrain, PassengerTrain};
+
+    /// Session kept in `journals/session.json`, run with `BLESS=1` to write
+    /// it again after an intended change.
+    fn session() -> Recorder {
+        let mut recorder = Recorder::new(TrainStation::new(2, LeastRecentlyUsed));
+        recorder.advance_to(Time::at(9, 0));
+        let first = recorder
+            .accept(PassengerTrain::new("RB 41".to_string()))
+            .unwrap();
+        recorder
+            .accept(FreightTrain::new("CT 42".to_string()))
+            .unwrap();
+        recorder
+            .accept(PassengerTrain::express("IC 43".to_string()))
+            .unwrap();
+        recorder
+            .accept(PassengerTrain::new("RB 44".to_string()))
+            .unwrap();
+        recorder.advance_to(Time::at(9, 5));
+        recorder.cancel("RB 44").unwrap();
+        recorder.depart(first).unwrap();
+        // Failed inputs are recorded too.
+        assert!(recorder.depart(first).is_err());
+        recorder.advance_to(Time::at(9, 20));
+        recorder.depart("CT 42").unwrap();
+        recorder.depart("IC 43").unwrap();
+        recorder
+            .accept(PassengerTrain::new("RB 45".to_string()))
+            .unwrap();
+
+        recorder
+    }
+
+    fn golden() -> Journal {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("journals/session.json");
+        if std::env::var_os("BLESS").is_some() {
+            session().journal().save(&path).unwrap();
+        }
+
+        Journal::load(&path).unwrap()
+    }
+
+    #[test]
+    fn session_matches_the_golden_journal() {
+        let recorder = session();
+
+        assert_eq!(golden(), *recorder.journal());
+    }
+
+    #[test]
+    fn golden_journal_replays_to_the_same_station() {
+        let replayed = golden().replay(LeastRecentlyUsed).unwrap();
+
+        assert_eq!(replayed.snapshot(), session().station().snapshot());
+    }
+
+    #[test]
+    fn replay_with_another_policy_diverges() {
+        assert!(matches!(
+            golden().replay(FirstFree),
+            Err(ReplayError::Diverged { .. })
+        ));
+    }
+
+    #[test]
+    fn handles_of_trains_from_before_the_recording_are_replayed() {
+        // Handles in another order than the names.
+        let mut station = TrainStation::new(1, FirstFree);
+        station
+            .accept(PassengerTrain::new("RB 9".to_string()))
+            .unwrap();
+        let waiting = station
+            .accept(FreightTrain::new("CT 3".to_string()))
+            .unwrap();
+
+        let mut recorder = Recorder::new(station);
+        recorder.depart(waiting).unwrap();
+        let journal = Journal::from_json(&recorder.journal().to_json()).unwrap();
+
+        let replayed = journal.replay(FirstFree).unwrap();
+        assert_eq!(replayed.snapshot(), recorder.station().snapshot());
+    }
+}
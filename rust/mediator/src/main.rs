@@ -0,0 +1,416 @@
+// Mediator
+// ref:
+// 1. https://refactoring.guru/design-patterns/mediator/rust/example
+mod actor;
+mod crossing;
+mod dashboard;
+mod deadlock;
+mod framework;
+mod journal;
+mod network;
+mod platform;
+#[cfg(test)]
+mod property;
+mod simulation;
+mod station;
+mod time;
+mod timetable;
+mod train;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use actor::StationActor;
+use crossing::{Barriers, CrossingMessage, SignalBox, TrackSensor};
+use framework::Hub;
+use journal::{Journal, Recorder};
+use network::{DeadlockResolution, RailNetwork};
+use platform::{DedicatedByKind, FirstFree, LeastRecentlyUsed, Platform};
+use simulation::Simulation;
+use station::{
+    Driver, EventLog, StationColleague, StationError, StationEvent, StationMessage,
+    StationSnapshot, TrainStation,
+};
+use time::Time;
+use timetable::TrainRegistry;
+use train::{FreightTrain, PassengerTrain, TrainKind};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    if std::env::args().any(|arg| arg == "--dashboard") {
+        dashboard::run();
+        return Ok(());
+    }
+
+    let train1 = PassengerTrain::new("train1".to_string());
+    let train2 = FreightTrain::new("train2".to_string());
+    let train3 = PassengerTrain::new("train3".to_string());
+
+    let mut station = TrainStation::new(2, LeastRecentlyUsed);
+    station.subscribe(|event| println!("{event}"));
+
+    station.accept(train1)?;
+    station.accept(train2)?;
+    station.accept(train3)?;
+
+    station.depart("train1")?;
+    station.depart("train2")?;
+    station.depart("train3")?;
+
+    if let Err(error) = station.depart("test train") {
+        println!("Error: {error}");
+    }
+
+    println!("\nTrains with names known only at runtime, managed by handle:\n");
+
+    let mut station = TrainStation::new(1, FirstFree);
+    station.subscribe(|event| println!("{event}"));
+
+    let handles = (1..=2)
+        .map(|number| station.accept(PassengerTrain::new(format!("RE {number}"))))
+        .collect::<Result<Vec<_>, _>>()?;
+    println!(
+        "'{name}' is {id}",
+        name = station.train(handles[1])?.name(),
+        id = handles[1]
+    );
+
+    station.depart(handles[0])?;
+    // The freed slot is reused, with a new generation.
+    let name = format!("CT {number}", number = handles.len() + 1);
+    let next = station.accept(FreightTrain::new(name.clone()))?;
+    println!(
+        "'{name}' is {next}, found by name: {found}",
+        found = station.id_of(&name) == Some(next)
+    );
+    if let Err(error) = station.depart(handles[0]) {
+        println!("Error: {error}");
+    }
+    station.depart(String::from("RE 2"))?;
+    station.depart(next)?;
+
+    println!("\nStation with platforms dedicated by train kind:\n");
+
+    let train4 = FreightTrain::new("train4".to_string());
+    let train5 = PassengerTrain::new("train5".to_string());
+    let train6 = FreightTrain::express("train6".to_string());
+
+    let mut station = TrainStation::new(
+        2,
+        DedicatedByKind::new(HashMap::from([
+            (TrainKind::Passenger, vec![0]),
+            (TrainKind::Freight, vec![1]),
+        ])),
+    );
+    station.subscribe(|event| println!("{event}"));
+
+    station.accept(train4)?;
+    station.accept(train5)?;
+    station.accept(train6)?;
+
+    // Leaves the waiting line, the platform stays with 'train4'
+    station.depart("train6")?;
+
+    station.depart("train4")?;
+    station.depart("train5")?;
+
+    println!("\nWaiting trains are admitted by priority:\n");
+
+    let mut station = TrainStation::default().with_aging(1);
+    let log = EventLog::default();
+    station.subscribe(log.subscriber());
+
+    station.accept(FreightTrain::new("train7".to_string()))?;
+    station.accept(FreightTrain::new("train8".to_string()))?;
+    station.accept(PassengerTrain::new("train9".to_string()))?;
+    station.accept(PassengerTrain::express("train10".to_string()))?;
+
+    station.depart("train7")?;
+    station.accept(PassengerTrain::new("train11".to_string()))?;
+    station.depart("train10")?;
+    station.depart("train9")?;
+    station.accept(PassengerTrain::new("train12".to_string()))?;
+    station.depart("train11")?;
+    station.depart("train8")?;
+    station.depart("train12")?;
+
+    for event in log.take() {
+        if let StationEvent::Admitted { train, platform } = event {
+            println!("'{train}' admitted to platform {platform}");
+        }
+    }
+
+    println!("\nWaiting trains diverted to another station or cancelled:\n");
+
+    let mut central = TrainStation::new(1, FirstFree).with_invariant_checks();
+    central.subscribe(|event| println!("Central: {event}"));
+    let mut northgate = TrainStation::new(1, FirstFree).with_invariant_checks();
+    northgate.subscribe(|event| println!("Northgate: {event}"));
+
+    central.advance_to(Time::at(8, 0));
+    central.accept(PassengerTrain::new("RB 21".to_string()))?;
+    central.accept(FreightTrain::new("CT 22".to_string()))?;
+    let express = central.accept(PassengerTrain::express("IC 23".to_string()))?;
+    central.accept(PassengerTrain::new("RB 24".to_string()))?;
+
+    northgate.advance_to(Time::at(8, 10));
+    northgate.accept(FreightTrain::new("CT 30".to_string()))?;
+    central.advance_to(Time::at(8, 10));
+    central.divert(express, &mut northgate)?;
+    central.divert("RB 24", &mut northgate)?;
+    central.cancel("CT 22")?;
+    if let Err(error) = central.cancel("RB 21") {
+        println!("Error: {error}");
+    }
+
+    northgate.advance_to(Time::at(8, 25));
+    northgate.depart("CT 30")?;
+    println!(
+        "Northgate waited {average:.1} min on average for passenger trains",
+        average = northgate.metrics().waits[&TrainKind::Passenger].average
+    );
+
+    println!("\nRecorded session saved and replayed:\n");
+
+    let mut recorder = Recorder::new(TrainStation::new(2, LeastRecentlyUsed));
+    recorder.advance_to(Time::at(9, 0));
+    let first = recorder.accept(PassengerTrain::new("RB 41".to_string()))?;
+    recorder.accept(FreightTrain::new("CT 42".to_string()))?;
+    recorder.accept(PassengerTrain::express("IC 43".to_string()))?;
+    recorder.accept(PassengerTrain::new("RB 44".to_string()))?;
+    recorder.advance_to(Time::at(9, 5));
+    recorder.cancel("RB 44")?;
+    recorder.depart(first)?;
+    // Failed inputs are recorded too.
+    let _ = recorder.depart(first);
+    recorder.advance_to(Time::at(9, 20));
+    recorder.depart("CT 42")?;
+    recorder.depart("IC 43")?;
+    recorder.accept(PassengerTrain::new("RB 45".to_string()))?;
+
+    let path = std::env::temp_dir().join("station-session.json");
+    recorder.journal().save(&path)?;
+    let journal = Journal::load(&path)?;
+    let replayed = journal.replay(LeastRecentlyUsed)?;
+    println!(
+        "{entries} inputs replayed with the same events, same station afterwards: {same}",
+        entries = journal.entries.len(),
+        same = replayed.snapshot() == recorder.station().snapshot()
+    );
+    if let Err(error) = journal.replay(FirstFree) {
+        println!("Replay with another policy: {error}");
+    }
+
+    println!("\nPlatforms of different length and use:\n");
+
+    let mut station = TrainStation::with_platforms(
+        vec![
+            Platform::new(200, &[TrainKind::Passenger]),
+            Platform::new(350, &[TrainKind::Passenger, TrainKind::Freight]),
+        ],
+        FirstFree,
+    );
+    station.subscribe(|event| println!("{event}"));
+
+    station.accept(FreightTrain::new("train13".to_string()))?;
+    if let Err(error) = station.accept(FreightTrain::new("train14".to_string()).with_cars(30)) {
+        println!("Error: {error}");
+    }
+    station.accept(PassengerTrain::new("train15".to_string()))?;
+    station.accept(PassengerTrain::new("train16".to_string()).with_cars(10))?;
+
+    let snapshot = station.snapshot();
+    let json = snapshot.to_json();
+    println!("\nSaved station:\n{json}\n");
+
+    let mut station = TrainStation::restore(StationSnapshot::from_json(&json)?, FirstFree)?;
+    station.subscribe(|event| println!("{event}"));
+    println!(
+        "Restored station is identical: {identical}\n",
+        identical = station.snapshot() == snapshot
+    );
+
+    station.depart("train15")?;
+    station.depart("train13")?;
+    station.depart("train16")?;
+
+    println!("\nTimetable simulation:\n");
+
+    let mut simulation = Simulation::new(TrainStation::new(2, FirstFree));
+
+    simulation.schedule(PassengerTrain::new("IC 101".to_string()), Time::at(8, 0), 5);
+    simulation.schedule(FreightTrain::new("CT 7".to_string()), Time::at(8, 2), 15);
+    simulation.schedule(
+        PassengerTrain::express("EX 3".to_string()),
+        Time::at(8, 4),
+        2,
+    );
+    simulation.schedule(PassengerTrain::new("IC 103".to_string()), Time::at(8, 5), 5);
+    simulation.schedule(FreightTrain::new("CT 9".to_string()), Time::at(8, 6), 10);
+
+    let report = simulation.run()?;
+    println!("{report}\n\n{metrics}\n", metrics = report.metrics);
+    print!("{csv}", csv = report.metrics.to_csv());
+
+    let timetables = Path::new(env!("CARGO_MANIFEST_DIR")).join("timetables");
+    let mut registry = TrainRegistry::default();
+    registry.register("railcar", |name| Box::new(PassengerTrain::new(name)));
+
+    for file in ["morning.csv", "morning.toml"] {
+        println!("\nTimetable '{file}':\n");
+
+        let mut simulation = Simulation::new(TrainStation::new(2, FirstFree));
+        simulation.load(timetable::load(&timetables.join(file), &registry)?);
+
+        println!("{report}", report = simulation.run()?);
+    }
+
+    let broken =
+        "name,kind,arrival,departure\nRB 12,railcar,07:58,08:01\nRB 14,railcar,8h05,08:07\n";
+    if let Err(error) = timetable::from_csv(broken, &registry) {
+        println!("\nBroken timetable: {error}");
+    }
+
+    println!("\nRail network:\n");
+
+    let mut network = RailNetwork::default();
+    network.subscribe(|event| println!("{event}"));
+
+    network.add_station("Ashford", TrainStation::new(1, FirstFree));
+    network.add_station("Brook", TrainStation::new(2, FirstFree));
+    network.add_station("Croft", TrainStation::new(2, FirstFree));
+    network.connect("Ashford", "Brook")?;
+    network.connect("Brook", "Croft")?;
+
+    network.enter(
+        PassengerTrain::new("IC 201".to_string()),
+        &["Ashford", "Brook", "Croft"],
+    )?;
+    network.enter(FreightTrain::new("CT 30".to_string()), &["Brook", "Croft"])?;
+    network.enter(PassengerTrain::new("RB 5".to_string()), &["Croft", "Brook"])?;
+
+    network.dispatch("CT 30")?;
+    network.dispatch("IC 201")?;
+    if let Err(error) = network.dispatch("RB 5") {
+        println!("Error: {error}");
+    }
+
+    network.step()?;
+    network.dispatch("RB 5")?;
+    network.step()?;
+    network.dispatch("IC 201")?;
+    network.step()?;
+
+    println!("\nDeadlock on a single-track section:\n");
+
+    let mut network =
+        RailNetwork::default().with_resolution(DeadlockResolution::BackOffLowestPriority);
+    network.subscribe(|event| println!("{event}"));
+
+    network.add_station("Ashford", TrainStation::new(2, FirstFree));
+    network.add_station("Brook", TrainStation::new(1, FirstFree));
+    network.connect("Ashford", "Brook")?;
+
+    network.enter(
+        PassengerTrain::new("IC 301".to_string()),
+        &["Ashford", "Brook"],
+    )?;
+    network.enter(
+        FreightTrain::new("CT 40".to_string()),
+        &["Brook", "Ashford"],
+    )?;
+
+    network.dispatch("IC 301")?;
+    network.step()?;
+    // Finds the deadlock, 'IC 301' backs off and clears the block.
+    network.dispatch("CT 40")?;
+    network.step()?;
+    network.dispatch("IC 301")?;
+    network.step()?;
+
+    println!("\nStation actor with a train per thread:\n");
+
+    let actor = StationActor::spawn(|| TrainStation::new(2, LeastRecentlyUsed));
+    let threads: Vec<_> = (0..4)
+        .map(|index| {
+            let station = actor.handle();
+
+            thread::spawn(move || -> Result<usize, StationError> {
+                let name = format!("train{index}");
+                let platform = station.arrive(Box::new(PassengerTrain::new(name.clone())))?;
+                thread::sleep(Duration::from_millis(1));
+                station.depart(&name)?;
+
+                Ok(platform)
+            })
+        })
+        .collect();
+
+    for (index, thread) in threads.into_iter().enumerate() {
+        let platform = thread.join().expect("train thread panicked")?;
+        println!("'train{index}' used platform {platform}");
+    }
+    actor.join();
+
+    println!("\nLevel crossing coordinated through a hub:\n");
+
+    let mut hub = Hub::default();
+    let barriers = hub.register(Barriers::default());
+    let signal_box = hub.register(SignalBox::new(barriers));
+    let sensor = hub.register(TrackSensor);
+
+    let closed = hub.request(
+        sensor,
+        signal_box,
+        CrossingMessage::Approaching("IC 301".to_string()),
+    )?;
+    println!("Crossing closed: {closed}");
+
+    hub.post(
+        sensor,
+        signal_box,
+        CrossingMessage::Passed("IC 301".to_string()),
+    )?;
+    let delivered = hub.run()?;
+    println!("{delivered} posted messages delivered");
+
+    hub.unregister(sensor)?;
+    if let Err(error) = hub.post(
+        sensor,
+        signal_box,
+        CrossingMessage::Passed("IC 301".to_string()),
+    ) {
+        println!("Error: {error}");
+    }
+
+    println!("\nStation on a hub, drivers told their platform:\n");
+
+    let mut hub = Hub::default();
+    let mut station = TrainStation::new(1, FirstFree);
+    station.subscribe(|event| println!("{event}"));
+    let station = hub.register(StationColleague::new(station));
+    let drivers = [hub.register(Driver), hub.register(Driver)];
+
+    hub.post(
+        drivers[0],
+        station,
+        StationMessage::Arrive(Box::new(PassengerTrain::new("RB 61".to_string()))),
+    )?;
+    hub.post(
+        drivers[1],
+        station,
+        StationMessage::Arrive(Box::new(FreightTrain::new("CT 62".to_string()))),
+    )?;
+    let mut delivered = hub.run()?;
+    hub.post(
+        drivers[0],
+        station,
+        StationMessage::Depart("RB 61".to_string()),
+    )?;
+    delivered += hub.run()?;
+    println!("{delivered} posted messages delivered");
+
+    Ok(())
+}
@@ -0,0 +1,621 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+
+use crate::deadlock::{Resource, WaitForGraph};
+use crate::station::{StationError, TrainStation};
+use crate::train::Train;
+
+/// Single-track section between two neighbouring stations. It is used in
+/// both directions, so it is identified by the unordered pair of stations.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockId(String, String);
+
+impl BlockId {
+    pub fn between(a: &str, b: &str) -> Self {
+        if a <= b {
+            Self(a.to_string(), b.to_string())
+        } else {
+            Self(b.to_string(), a.to_string())
+        }
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.0, self.1)
+    }
+}
+
+#[derive(Default)]
+struct Block {
+    occupant: Option<BlockOccupant>,
+}
+
+struct BlockOccupant {
+    train: Box<dyn Train>,
+    from: String,
+    to: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Location {
+    Station(String),
+    Block(BlockId),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkEvent {
+    EnteredBlock {
+        train: String,
+        block: BlockId,
+    },
+    /// The train is waiting in the block for a platform at the next station.
+    HeldInBlock {
+        train: String,
+        block: BlockId,
+    },
+    LeftBlock {
+        train: String,
+        block: BlockId,
+    },
+    RouteCompleted {
+        train: String,
+        station: String,
+    },
+    /// The trains wait for each other and none of them can move on.
+    Deadlock {
+        trains: Vec<String>,
+    },
+    /// The train went back to the station it came from to break a deadlock.
+    BackedOff {
+        train: String,
+        block: BlockId,
+    },
+}
+
+impl fmt::Display for NetworkEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EnteredBlock { train, block } => write!(f, "'{train}': Entered block {block}"),
+            Self::HeldInBlock { train, block } => {
+                write!(
+                    f,
+                    "'{train}': Held in block {block}, waiting for a platform..."
+                )
+            }
+            Self::LeftBlock { train, block } => write!(f, "'{train}': Left block {block}"),
+            Self::RouteCompleted { train, station } => {
+                write!(f, "'{train}': Route completed at '{station}'")
+            }
+            Self::Deadlock { trains } => write!(f, "Deadlock between '{}'", trains.join("', '")),
+            Self::BackedOff { train, block } => {
+                write!(f, "'{train}': Backed off from block {block}")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    UnknownStation(String),
+    UnknownTrain(String),
+    DuplicateTrain(String),
+    NoBlock(BlockId),
+    BlockOccupied {
+        block: BlockId,
+        train: String,
+    },
+    /// The train has to be on a platform to be dispatched.
+    NotOnPlatform(String),
+    RouteCompleted(String),
+    Station(StationError),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownStation(name) => write!(f, "There is no station '{name}'"),
+            Self::UnknownTrain(name) => write!(f, "'{name}' is not on the network"),
+            Self::DuplicateTrain(name) => write!(f, "'{name}' is already on the network"),
+            Self::NoBlock(block) => write!(f, "There is no track {block}"),
+            Self::BlockOccupied { block, train } => {
+                write!(f, "Block {block} is occupied by '{train}'")
+            }
+            Self::NotOnPlatform(name) => write!(f, "'{name}' is not on a platform"),
+            Self::RouteCompleted(name) => write!(f, "'{name}' has no further station to go"),
+            Self::Station(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<StationError> for NetworkError {
+    fn from(error: StationError) -> Self {
+        Self::Station(error)
+    }
+}
+
+type Subscriber = Box<dyn FnMut(&NetworkEvent)>;
+
+/// What the network does when it finds a deadlock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeadlockResolution {
+    /// Only report the deadlocked trains.
+    #[default]
+    Report,
+    /// Send the train with the lowest priority which waits in a block back to
+    /// the station it came from.
+    BackOffLowestPriority,
+}
+
+/// Mediator of a rail line. It owns the stations and the blocks between them
+/// and moves trains along their routes. A block takes one train at a time, and
+/// the train keeps the block until the next station gives it a platform.
+#[derive(Default)]
+pub struct RailNetwork {
+    stations: BTreeMap<String, TrainStation>,
+    blocks: BTreeMap<BlockId, Block>,
+    locations: HashMap<String, Location>,
+    // Stations the train still has to visit.
+    routes: HashMap<String, VecDeque<String>>,
+    // Resource a train has been refused, kept until the train moves on.
+    waits: HashMap<String, Resource>,
+    resolution: DeadlockResolution,
+    subscribers: Vec<Subscriber>,
+}
+
+impl RailNetwork {
+    pub fn with_resolution(mut self, resolution: DeadlockResolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn add_station(&mut self, name: &str, station: TrainStation) {
+        self.stations.insert(name.to_string(), station);
+    }
+
+    /// Lays a block between two stations.
+    pub fn connect(&mut self, a: &str, b: &str) -> Result<(), NetworkError> {
+        for station in [a, b] {
+            if !self.stations.contains_key(station) {
+                return Err(NetworkError::UnknownStation(station.to_string()));
+            }
+        }
+
+        self.blocks.insert(BlockId::between(a, b), Block::default());
+
+        Ok(())
+    }
+
+    pub fn subscribe(&mut self, subscriber: impl FnMut(&NetworkEvent) + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    fn emit(&mut self, event: NetworkEvent) {
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event);
+        }
+    }
+
+    /// Puts the train on the first station of its route.
+    pub fn enter(
+        &mut self,
+        train: impl Train + 'static,
+        route: &[&str],
+    ) -> Result<(), NetworkError> {
+        if self.locations.contains_key(train.name()) {
+            return Err(NetworkError::DuplicateTrain(train.name().to_string()));
+        }
+
+        let mut route: VecDeque<String> = route.iter().map(|name| name.to_string()).collect();
+        let first = route
+            .pop_front()
+            .ok_or_else(|| NetworkError::RouteCompleted(train.name().to_string()))?;
+        for station in std::iter::once(&first).chain(&route) {
+            // Otherwise the train would be held in the block before the
+            // station forever.
+            self.stations
+                .get(station)
+                .ok_or_else(|| NetworkError::UnknownStation(station.clone()))?
+                .check_platforms(&train)?;
+        }
+
+        let name = train.name().to_string();
+        self.stations
+            .get_mut(&first)
+            .ok_or_else(|| NetworkError::UnknownStation(first.clone()))?
+            .accept(train)?;
+
+        self.locations
+            .insert(name.clone(), Location::Station(first));
+        self.routes.insert(name, route);
+
+        Ok(())
+    }
+
+    /// Releases the train from its platform onto the block towards the next
+    /// station of its route, if that block is clear.
+    pub fn dispatch(&mut self, train: &str) -> Result<(), NetworkError> {
+        let Some(Location::Station(from)) = self.locations.get(train).cloned() else {
+            return Err(NetworkError::UnknownTrain(train.to_string()));
+        };
+        let to = self
+            .routes
+            .get(train)
+            .and_then(|route| route.front())
+            .cloned()
+            .ok_or_else(|| NetworkError::RouteCompleted(train.to_string()))?;
+
+        let on_platform = self
+            .stations
+            .get(&from)
+            .ok_or_else(|| NetworkError::UnknownStation(from.clone()))?
+            .platform_of(train)
+            .is_some();
+        if !on_platform {
+            return Err(NetworkError::NotOnPlatform(train.to_string()));
+        }
+
+        let id = BlockId::between(&from, &to);
+        if !self.blocks.contains_key(&id) {
+            return Err(NetworkError::NoBlock(id));
+        }
+        if self.blocks[&id].occupant.is_some() {
+            self.waits
+                .insert(train.to_string(), Resource::Block(id.clone()));
+            self.check_deadlock()?;
+
+            // Breaking a deadlock may have cleared the block.
+            if let Some(occupant) = &self.blocks[&id].occupant {
+                return Err(NetworkError::BlockOccupied {
+                    block: id.clone(),
+                    train: occupant.train.name().to_string(),
+                });
+            }
+        }
+
+        let released = self
+            .stations
+            .get_mut(&from)
+            .ok_or_else(|| NetworkError::UnknownStation(from.clone()))?
+            .release(train)?;
+
+        if let Some(route) = self.routes.get_mut(train) {
+            route.pop_front();
+        }
+        if let Some(block) = self.blocks.get_mut(&id) {
+            block.occupant = Some(BlockOccupant {
+                train: released,
+                from,
+                to,
+            });
+        }
+        self.waits.remove(train);
+        self.locations
+            .insert(train.to_string(), Location::Block(id.clone()));
+        self.emit(NetworkEvent::EnteredBlock {
+            train: train.to_string(),
+            block: id,
+        });
+
+        Ok(())
+    }
+
+    /// Moves every train waiting in a block onto the next station, if it has a
+    /// free platform. Returns how many trains have moved.
+    pub fn step(&mut self) -> Result<usize, NetworkError> {
+        let occupied: Vec<BlockId> = self
+            .blocks
+            .iter()
+            .filter(|(_, block)| block.occupant.is_some())
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut moved = 0;
+        let mut held = false;
+        for id in occupied {
+            let Some(occupant) = self
+                .blocks
+                .get_mut(&id)
+                .and_then(|block| block.occupant.take())
+            else {
+                continue;
+            };
+            let name = occupant.train.name().to_string();
+
+            let station = self
+                .stations
+                .get_mut(&occupant.to)
+                .ok_or_else(|| NetworkError::UnknownStation(occupant.to.clone()))?;
+            if !station.can_admit(occupant.train.as_ref()) {
+                self.waits
+                    .insert(name.clone(), Resource::Platforms(occupant.to.clone()));
+                if let Some(block) = self.blocks.get_mut(&id) {
+                    block.occupant = Some(occupant);
+                }
+                held = true;
+                self.emit(NetworkEvent::HeldInBlock {
+                    train: name,
+                    block: id,
+                });
+                continue;
+            }
+
+            // The train stays in the block if the station refuses it.
+            if let Err(error) = station.check_arrival(occupant.train.as_ref()) {
+                if let Some(block) = self.blocks.get_mut(&id) {
+                    block.occupant = Some(occupant);
+                }
+                return Err(error.into());
+            }
+
+            // Only the invariant checks can fail now, the train is on the
+            // station either way.
+            let accepted = station.accept_boxed(occupant.train);
+            self.waits.remove(&name);
+            self.locations
+                .insert(name.clone(), Location::Station(occupant.to.clone()));
+            accepted?;
+            moved += 1;
+
+            self.emit(NetworkEvent::LeftBlock {
+                train: name.clone(),
+                block: id,
+            });
+            if self.routes.get(&name).is_some_and(VecDeque::is_empty) {
+                self.emit(NetworkEvent::RouteCompleted {
+                    train: name,
+                    station: occupant.to,
+                });
+            }
+        }
+
+        if held {
+            self.check_deadlock()?;
+        }
+
+        Ok(moved)
+    }
+
+    /// Trains which wait for each other in a circle, sorted by name.
+    pub fn deadlocked(&self) -> Vec<String> {
+        let mut graph = WaitForGraph::default();
+
+        for (train, resource) in &self.waits {
+            graph.wait(train, resource.clone());
+        }
+        for (train, location) in &self.locations {
+            // A train registered at a station without a platform is queued.
+            if let Location::Station(name) = location {
+                let station = &self.stations[name];
+                if station.platform_of(train).is_none() {
+                    graph.wait(train, Resource::Platforms(name.clone()));
+                }
+            }
+        }
+        for (name, station) in &self.stations {
+            for train in station.occupants() {
+                graph.hold(Resource::Platforms(name.clone()), train);
+            }
+        }
+        for (id, block) in &self.blocks {
+            if let Some(occupant) = &block.occupant {
+                graph.hold(Resource::Block(id.clone()), occupant.train.name());
+            }
+        }
+
+        graph.deadlocked()
+    }
+
+    fn check_deadlock(&mut self) -> Result<(), NetworkError> {
+        let trains = self.deadlocked();
+        if trains.is_empty() {
+            return Ok(());
+        }
+
+        self.emit(NetworkEvent::Deadlock {
+            trains: trains.clone(),
+        });
+
+        if self.resolution == DeadlockResolution::BackOffLowestPriority {
+            let victim = self
+                .blocks
+                .iter()
+                .filter_map(|(id, block)| {
+                    let occupant = block.occupant.as_ref()?;
+                    let name = occupant.train.name();
+                    trains
+                        .iter()
+                        .any(|train| train == name)
+                        .then(|| (occupant.train.priority(), name.to_string(), id.clone()))
+                })
+                .min();
+
+            if let Some((_, _, id)) = victim {
+                self.back_off(&id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the train in the block to the station it came from, where it
+    /// gets the next station of its route again.
+    fn back_off(&mut self, id: &BlockId) -> Result<(), NetworkError> {
+        let Some(occupant) = self
+            .blocks
+            .get_mut(id)
+            .and_then(|block| block.occupant.take())
+        else {
+            return Ok(());
+        };
+        let name = occupant.train.name().to_string();
+
+        if let Some(route) = self.routes.get_mut(&name) {
+            route.push_front(occupant.to);
+        }
+        self.stations
+            .get_mut(&occupant.from)
+            .ok_or_else(|| NetworkError::UnknownStation(occupant.from.clone()))?
+            .accept_boxed(occupant.train)?;
+        self.locations
+            .insert(name.clone(), Location::Station(occupant.from));
+        self.waits.remove(&name);
+        self.emit(NetworkEvent::BackedOff {
+            train: name,
+            block: id.clone(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{DedicatedByKind, FirstFree, Platform};
+    use crate::train::{FreightTrain, PassengerTrain, TrainKind};
+
+    fn line(brook: TrainStation) -> RailNetwork {
+        let mut network = RailNetwork::default();
+        network.add_station("Ashford", TrainStation::new(1, FirstFree));
+        network.add_station("Brook", brook);
+        network.connect("Ashford", "Brook").unwrap();
+        network
+    }
+
+    #[test]
+    fn train_already_on_the_network_cannot_enter_again() {
+        let mut network = line(TrainStation::new(1, FirstFree));
+        network
+            .enter(
+                PassengerTrain::new("RB 1".to_string()),
+                &["Ashford", "Brook"],
+            )
+            .unwrap();
+
+        assert_eq!(
+            network.enter(PassengerTrain::new("RB 1".to_string()), &["Brook"]),
+            Err(NetworkError::DuplicateTrain("RB 1".to_string()))
+        );
+        assert_eq!(
+            network.locations["RB 1"],
+            Location::Station("Ashford".to_string())
+        );
+    }
+
+    #[test]
+    fn queued_train_is_not_dispatched_and_does_not_wait_for_the_block() {
+        let mut network = line(TrainStation::new(1, FirstFree));
+        for name in ["RB 1", "RB 2", "RB 3"] {
+            network
+                .enter(PassengerTrain::new(name.to_string()), &["Ashford", "Brook"])
+                .unwrap();
+        }
+        network.dispatch("RB 1").unwrap();
+
+        assert_eq!(
+            network.dispatch("RB 3"),
+            Err(NetworkError::NotOnPlatform("RB 3".to_string()))
+        );
+        assert!(!network.waits.contains_key("RB 3"));
+    }
+
+    #[test]
+    fn dispatch_goes_ahead_once_a_deadlock_is_broken() {
+        let mut network = line(TrainStation::new(1, FirstFree))
+            .with_resolution(DeadlockResolution::BackOffLowestPriority);
+        network
+            .enter(
+                PassengerTrain::new("IC 1".to_string()),
+                &["Ashford", "Brook"],
+            )
+            .unwrap();
+        network
+            .enter(
+                PassengerTrain::express("IC 2".to_string()),
+                &["Brook", "Ashford"],
+            )
+            .unwrap();
+        network.dispatch("IC 1").unwrap();
+        network.step().unwrap();
+
+        assert_eq!(network.dispatch("IC 2"), Ok(()));
+        assert_eq!(
+            network.locations["IC 1"],
+            Location::Station("Ashford".to_string())
+        );
+    }
+
+    #[test]
+    fn train_no_station_on_the_route_fits_cannot_enter() {
+        let brook = TrainStation::with_platforms(
+            vec![Platform::new(100, &[TrainKind::Passenger])],
+            FirstFree,
+        );
+        let mut network = line(brook);
+
+        let entered = network.enter(
+            PassengerTrain::new("IC 1".to_string()),
+            &["Ashford", "Brook"],
+        );
+
+        assert!(matches!(
+            entered,
+            Err(NetworkError::Station(
+                StationError::NoCompatiblePlatform { .. }
+            ))
+        ));
+        assert!(network.locations.is_empty());
+    }
+
+    #[test]
+    fn train_refused_by_the_next_station_stays_in_the_block() {
+        // A train of the same name got onto Brook outside the network.
+        let mut brook = TrainStation::new(2, FirstFree);
+        brook
+            .accept(PassengerTrain::new("RB 1".to_string()))
+            .unwrap();
+        let mut network = line(brook);
+        network
+            .enter(
+                PassengerTrain::new("RB 1".to_string()),
+                &["Ashford", "Brook"],
+            )
+            .unwrap();
+        network.dispatch("RB 1").unwrap();
+
+        assert_eq!(
+            network.step(),
+            Err(NetworkError::Station(StationError::DuplicateTrain(
+                "RB 1".to_string()
+            )))
+        );
+        let id = BlockId::between("Ashford", "Brook");
+        assert!(network.blocks[&id].occupant.is_some());
+        assert_eq!(network.locations["RB 1"], Location::Block(id));
+    }
+
+    #[test]
+    fn train_the_policy_only_sends_to_platforms_too_short_cannot_enter() {
+        let brook = TrainStation::with_platforms(
+            vec![
+                Platform::default(),
+                Platform::new(100, &[TrainKind::Passenger, TrainKind::Freight]),
+            ],
+            DedicatedByKind::new(HashMap::from([(TrainKind::Freight, vec![1])])),
+        );
+        let mut network = line(brook);
+
+        let entered = network.enter(FreightTrain::new("CT 1".to_string()), &["Ashford", "Brook"]);
+
+        assert!(matches!(
+            entered,
+            Err(NetworkError::Station(
+                StationError::NoCompatiblePlatform { .. }
+            ))
+        ));
+        assert!(network.locations.is_empty());
+    }
+}
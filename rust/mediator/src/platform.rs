@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::train::{Train, TrainKind};
+
+/// Platform assignment policy of a station.
+pub trait PlatformAssignment {
+    /// Picks a free platform for the train, or `None` if the train has to wait.
+    fn assign(&self, train: &dyn Train, platforms: &[Platform]) -> Option<usize>;
+
+    /// Platforms the policy could ever give the train, free or not.
+    fn candidates(&self, _train: &dyn Train, platforms: &[Platform]) -> Vec<usize> {
+        (0..platforms.len()).collect()
+    }
+}
+
+/// Takes the free platform with the lowest number.
+pub struct FirstFree;
+
+impl PlatformAssignment for FirstFree {
+    fn assign(&self, train: &dyn Train, platforms: &[Platform]) -> Option<usize> {
+        platforms.iter().position(|platform| platform.takes(train))
+    }
+}
+
+/// Takes the free platform which has been free for the longest time, so the
+/// wear is spread over all platforms.
+pub struct LeastRecentlyUsed;
+
+impl PlatformAssignment for LeastRecentlyUsed {
+    fn assign(&self, train: &dyn Train, platforms: &[Platform]) -> Option<usize> {
+        platforms
+            .iter()
+            .enumerate()
+            .filter(|(_, platform)| platform.takes(train))
+            .min_by_key(|(_, platform)| platform.last_departure)
+            .map(|(index, _)| index)
+    }
+}
+
+/// Reserves platforms for a kind of train. Kinds without dedicated platforms
+/// can use any free platform which is not dedicated to another kind.
+pub struct DedicatedByKind {
+    dedicated: HashMap<TrainKind, Vec<usize>>,
+}
+
+impl DedicatedByKind {
+    pub fn new(dedicated: HashMap<TrainKind, Vec<usize>>) -> Self {
+        Self { dedicated }
+    }
+}
+
+impl PlatformAssignment for DedicatedByKind {
+    fn assign(&self, train: &dyn Train, platforms: &[Platform]) -> Option<usize> {
+        match self.dedicated.get(&train.kind()) {
+            Some(indexes) => indexes
+                .iter()
+                .copied()
+                .find(|&index| platforms.get(index).is_some_and(|p| p.takes(train))),
+            None => platforms.iter().enumerate().position(|(index, platform)| {
+                platform.takes(train)
+                    && !self
+                        .dedicated
+                        .values()
+                        .any(|indexes| indexes.contains(&index))
+            }),
+        }
+    }
+
+    fn candidates(&self, train: &dyn Train, platforms: &[Platform]) -> Vec<usize> {
+        match self.dedicated.get(&train.kind()) {
+            Some(indexes) => indexes
+                .iter()
+                .copied()
+                .filter(|&index| index < platforms.len())
+                .collect(),
+            None => (0..platforms.len())
+                .filter(|index| {
+                    !self
+                        .dedicated
+                        .values()
+                        .any(|indexes| indexes.contains(index))
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct Platform {
+    pub train: Option<String>,
+    // Number of the station departure which left this platform free.
+    pub last_departure: usize,
+    /// Usable length in metres.
+    pub length: u32,
+    pub kinds: Vec<TrainKind>,
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::new(u32::MAX, &[TrainKind::Passenger, TrainKind::Freight])
+    }
+}
+
+impl Platform {
+    pub fn new(length: u32, kinds: &[TrainKind]) -> Self {
+        Self {
+            train: None,
+            last_departure: 0,
+            length,
+            kinds: kinds.to_vec(),
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.train.is_none()
+    }
+
+    /// Whether the train could ever use the platform.
+    pub fn check(&self, train: &dyn Train) -> Result<(), Incompatibility> {
+        if !self.kinds.contains(&train.kind()) {
+            return Err(Incompatibility::KindNotAllowed(train.kind()));
+        }
+        if train.length() > self.length {
+            return Err(Incompatibility::TooShort {
+                platform: self.length,
+                train: train.length(),
+                cars: train.cars(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether the train can use the platform right now.
+    pub fn takes(&self, train: &dyn Train) -> bool {
+        self.is_free() && self.check(train).is_ok()
+    }
+}
+
+/// Why a train can never use a platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Incompatibility {
+    KindNotAllowed(TrainKind),
+    /// The assignment policy never gives the platform to this kind of train.
+    NotAssigned(TrainKind),
+    /// Lengths in metres.
+    TooShort {
+        platform: u32,
+        train: u32,
+        cars: u32,
+    },
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::KindNotAllowed(kind) => write!(f, "takes no {kind} trains"),
+            Self::NotAssigned(kind) => write!(f, "is not assigned to {kind} trains"),
+            Self::TooShort {
+                platform,
+                train,
+                cars,
+            } => write!(
+                f,
+                "is {platform} m long but the train is {train} m with {cars} cars"
+            ),
+        }
+    }
+}
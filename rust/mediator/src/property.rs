@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::platform::{FirstFree, LeastRecentlyUsed, Platform};
+use crate::station::{StationError, TrainStation};
+use crate::time::Time;
+use crate::train::{TrainKind, TrainSpec};
+
+const NAMES: usize = 8;
+
+/// Small xorshift generator, so that a failing run can be repeated from its
+/// seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero would only ever give zeros.
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Operation {
+    Accept { station: usize, train: TrainSpec },
+    Depart { station: usize, train: String },
+    Cancel { station: usize, train: String },
+    Divert { from: usize, train: String },
+    AdvanceTo(Time),
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Accept { station, train } => write!(
+                f,
+                "station {station} accepts '{name}' ({kind}, {cars} cars{express})",
+                name = train.name,
+                kind = train.kind,
+                cars = train.cars,
+                express = if train.express { ", express" } else { "" }
+            ),
+            Self::Depart { station, train } => write!(f, "'{train}' departs station {station}"),
+            Self::Cancel { station, train } => write!(f, "station {station} cancels '{train}'"),
+            Self::Divert { from, train } => {
+                write!(
+                    f,
+                    "station {from} diverts '{train}' to station {to}",
+                    to = 1 - from
+                )
+            }
+            Self::AdvanceTo(time) => write!(f, "clock advances to {time}"),
+        }
+    }
+}
+
+/// Operations of a random run up to the one which broke a property.
+pub struct Counterexample {
+    pub seed: u64,
+    pub operations: Vec<Operation>,
+    pub problem: String,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Seed {seed}: {problem}, after:",
+            seed = self.seed,
+            problem = self.problem
+        )?;
+        for operation in &self.operations {
+            writeln!(f, "  {operation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `runs` random sequences of `steps` operations on two connected
+/// stations with invariant checks on, starting from `seed`. After every
+/// operation the outcome is compared with a plain model of which train is on
+/// which station. Returns how many operations were run.
+pub fn check(seed: u64, runs: usize, steps: usize) -> Result<usize, Counterexample> {
+    for run in 0..runs as u64 {
+        explore(seed + run, steps)?;
+    }
+
+    Ok(runs * steps)
+}
+
+fn explore(seed: u64, steps: usize) -> Result<(), Counterexample> {
+    let mut rng = Rng::new(seed);
+    let mut stations = [random_station(&mut rng), random_station(&mut rng)];
+    // Station and name of every train, as far as the stations have said so.
+    let mut model: HashSet<(usize, String)> = HashSet::new();
+    let mut operations = Vec::new();
+    let mut now = Time::default();
+
+    for _ in 0..steps {
+        let operation = random_operation(&mut rng, &mut now);
+        operations.push(operation.clone());
+
+        if let Err(problem) =
+            apply(&mut stations, &mut model, &operation).and_then(|()| compare(&stations, &model))
+        {
+            return Err(Counterexample {
+                seed,
+                operations,
+                problem,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn random_station(rng: &mut Rng) -> TrainStation {
+    let kinds: [&[TrainKind]; 3] = [
+        &[TrainKind::Passenger],
+        &[TrainKind::Freight],
+        &[TrainKind::Passenger, TrainKind::Freight],
+    ];
+    let platforms = (0..1 + rng.below(3))
+        .map(|_| Platform::new(rng.pick(&[150, 250, 400]), rng.pick(&kinds)))
+        .collect();
+
+    let station = if rng.below(2) == 0 {
+        TrainStation::with_platforms(platforms, FirstFree)
+    } else {
+        TrainStation::with_platforms(platforms, LeastRecentlyUsed)
+    };
+    station.with_aging(1 + rng.below(3)).with_invariant_checks()
+}
+
+fn random_operation(rng: &mut Rng, now: &mut Time) -> Operation {
+    let station = rng.below(2);
+    let train = format!("T{number}", number = rng.below(NAMES));
+
+    match rng.below(10) {
+        0..=3 => Operation::Accept {
+            station,
+            train: TrainSpec {
+                name: train,
+                kind: rng.pick(&[TrainKind::Passenger, TrainKind::Freight]),
+                express: rng.below(3) == 0,
+                cars: 1 + rng.below(20) as u32,
+            },
+        },
+        4..=6 => Operation::Depart { station, train },
+        7 => Operation::Cancel { station, train },
+        8 => Operation::Divert {
+            from: station,
+            train,
+        },
+        _ => {
+            *now = now.after(rng.below(15) as u32);
+            Operation::AdvanceTo(*now)
+        }
+    }
+}
+
+fn apply(
+    stations: &mut [TrainStation; 2],
+    model: &mut HashSet<(usize, String)>,
+    operation: &Operation,
+) -> Result<(), String> {
+    match operation {
+        Operation::Accept { station, train } => {
+            let fits = stations[*station]
+                .platforms()
+                .iter()
+                .any(|platform| platform.check(train.build().as_ref()).is_ok());
+            let key = (*station, train.name.clone());
+            let success = fits && !model.contains(&key);
+            expect(
+                stations[*station].accept_boxed(train.build()).map(drop),
+                success,
+            )?;
+            if success {
+                model.insert(key);
+            }
+        }
+        Operation::Depart { station, train } => {
+            let present = model.remove(&(*station, train.clone()));
+            expect(stations[*station].depart(train), present)?;
+        }
+        Operation::Cancel { station, train } => {
+            let waiting = stations[*station].waiting().any(|name| name == train);
+            expect(stations[*station].cancel(train), waiting)?;
+            if waiting {
+                model.remove(&(*station, train.clone()));
+            }
+        }
+        Operation::Divert { from, train } => {
+            let waiting = stations[*from].waiting().any(|name| name == train);
+            let [first, second] = stations;
+            let (origin, target) = if *from == 0 {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let fits = origin.train(train).is_ok_and(|candidate| {
+                target
+                    .platforms()
+                    .iter()
+                    .any(|platform| platform.check(candidate).is_ok())
+            });
+            let success = waiting && fits && !model.contains(&(1 - from, train.clone()));
+            expect(origin.divert(train, target).map(drop), success)?;
+            if success {
+                model.remove(&(*from, train.clone()));
+                model.insert((1 - from, train.clone()));
+            }
+        }
+        Operation::AdvanceTo(time) => {
+            for station in stations.iter_mut() {
+                station.advance_to(*time);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compares the outcome with what the model expects. Inconsistencies found by
+/// the invariant checks always count as failures.
+fn expect(result: Result<(), StationError>, success: bool) -> Result<(), String> {
+    match (result, success) {
+        (Ok(()), true) => Ok(()),
+        (Err(error @ StationError::Inconsistency(_)), _) => Err(error.to_string()),
+        (Err(_), false) => Ok(()),
+        (Ok(()), false) => Err("operation succeeded, but should have failed".to_string()),
+        (Err(error), true) => Err(format!("operation failed: {error}")),
+    }
+}
+
+fn compare(stations: &[TrainStation; 2], model: &HashSet<(usize, String)>) -> Result<(), String> {
+    for (index, station) in stations.iter().enumerate() {
+        station
+            .check_invariants()
+            .map_err(|error| format!("station {index}: {error}"))?;
+
+        for number in 0..NAMES {
+            let name = format!("T{number}");
+            let expected = model.contains(&(index, name.clone()));
+            if station.train(name.as_str()).is_ok() != expected {
+                return Err(format!(
+                    "station {index} {has} '{name}'",
+                    has = if expected { "lost" } else { "has unexpected" }
+                ));
+            }
+        }
+
+        // No train waits while a platform it fits is free.
+        for name in station.waiting() {
+            let train = station.train(name).map_err(|error| error.to_string())?;
+            if let Some(platform) = station
+                .platforms()
+                .iter()
+                .position(|platform| platform.is_free() && platform.takes(train))
+            {
+                return Err(format!(
+                    "station {index}: '{name}' waits although platform {platform} is free"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn station_properties_hold_for_random_operations() {
+    if let Err(counterexample) = check(2024, 200, 100) {
+        panic!("{counterexample}");
+    }
+}
+
+#[test]
+fn station_properties_hold_for_long_runs() {
+    if let Err(counterexample) = check(7, 20, 2000) {
+        panic!("{counterexample}");
+    }
+}
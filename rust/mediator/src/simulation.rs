@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+
+use crate::station::{EventLog, StationError, StationEvent, StationMetrics, TrainStation};
+use crate::time::Time;
+use crate::timetable::TimetableEntry;
+use crate::train::Train;
+
+/// Discrete-event simulation of a station running a timetable on a virtual
+/// clock. Trains arrive at their scheduled time, the station decides when
+/// they get a platform, and each train stays there for its dwell time.
+pub struct Simulation {
+    station: TrainStation,
+    log: EventLog,
+    clock: Time,
+    agenda: BinaryHeap<Reverse<Occurrence>>,
+    timetable: Vec<ScheduledTrain>,
+    // Train name to the timetable row of its latest arrival, a name can run
+    // more than once a day.
+    current: HashMap<String, usize>,
+}
+
+struct ScheduledTrain {
+    train: Option<Box<dyn Train>>,
+    name: String,
+    arrival: Time,
+    dwell: u32,
+    actual_arrival: Option<Time>,
+    actual_departure: Option<Time>,
+    refused: Option<StationError>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Occurrence {
+    time: Time,
+    action: Action,
+}
+
+// Departures are ordered first, so a platform freed at the same minute can be
+// used by an arriving train.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Action {
+    Depart(usize),
+    Arrive(usize),
+}
+
+impl Simulation {
+    pub fn new(mut station: TrainStation) -> Self {
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+
+        Self {
+            station,
+            log,
+            clock: Time::default(),
+            agenda: BinaryHeap::new(),
+            timetable: Vec::new(),
+            current: HashMap::new(),
+        }
+    }
+
+    pub fn schedule(&mut self, train: impl Train + 'static, arrival: Time, dwell: u32) {
+        self.schedule_boxed(Box::new(train), arrival, dwell);
+    }
+
+    pub fn schedule_boxed(&mut self, train: Box<dyn Train>, arrival: Time, dwell: u32) {
+        let index = self.timetable.len();
+        self.timetable.push(ScheduledTrain {
+            name: train.name().to_string(),
+            train: Some(train),
+            arrival,
+            dwell,
+            actual_arrival: None,
+            actual_departure: None,
+            refused: None,
+        });
+        self.agenda.push(Reverse(Occurrence {
+            time: arrival,
+            action: Action::Arrive(index),
+        }));
+    }
+
+    pub fn load(&mut self, timetable: Vec<TimetableEntry>) {
+        for entry in timetable {
+            if let Some(platform) = entry.platform {
+                self.station.prefer_platform(entry.train.name(), platform);
+            }
+
+            let dwell = entry.departure.minutes_since(entry.arrival);
+            self.schedule_boxed(entry.train, entry.arrival, dwell);
+        }
+    }
+
+    pub fn run(mut self) -> Result<SimulationReport, StationError> {
+        while let Some(Reverse(occurrence)) = self.agenda.pop() {
+            self.clock = occurrence.time;
+            self.station.advance_to(self.clock);
+
+            match occurrence.action {
+                Action::Arrive(index) => {
+                    if let Some(train) = self.timetable[index].train.take() {
+                        // A refused train only misses its own row.
+                        match self.station.accept_boxed(train) {
+                            Ok(_) => {
+                                let name = self.timetable[index].name.clone();
+                                self.current.insert(name, index);
+                            }
+                            Err(error) => self.timetable[index].refused = Some(error),
+                        }
+                    }
+                }
+                Action::Depart(index) => {
+                    self.station.depart(&self.timetable[index].name)?;
+                    self.timetable[index].actual_departure = Some(self.clock);
+                }
+            }
+
+            for event in self.log.take() {
+                if let StationEvent::Admitted { train, .. } = event {
+                    self.on_admitted(&train);
+                }
+            }
+        }
+
+        Ok(SimulationReport {
+            metrics: self.station.metrics(),
+            rows: self
+                .timetable
+                .into_iter()
+                .map(|scheduled| TrainReport {
+                    name: scheduled.name,
+                    scheduled_arrival: scheduled.arrival,
+                    actual_arrival: scheduled.actual_arrival,
+                    scheduled_departure: scheduled.arrival.after(scheduled.dwell),
+                    actual_departure: scheduled.actual_departure,
+                    refused: scheduled.refused,
+                })
+                .collect(),
+        })
+    }
+
+    fn on_admitted(&mut self, name: &str) {
+        let Some(&index) = self.current.get(name) else {
+            return;
+        };
+
+        let scheduled = &mut self.timetable[index];
+        scheduled.actual_arrival = Some(self.clock);
+        self.agenda.push(Reverse(Occurrence {
+            time: self.clock.after(scheduled.dwell),
+            action: Action::Depart(index),
+        }));
+    }
+}
+
+pub struct SimulationReport {
+    rows: Vec<TrainReport>,
+    pub metrics: StationMetrics,
+}
+
+pub struct TrainReport {
+    name: String,
+    scheduled_arrival: Time,
+    actual_arrival: Option<Time>,
+    scheduled_departure: Time,
+    actual_departure: Option<Time>,
+    refused: Option<StationError>,
+}
+
+impl TrainReport {
+    /// Minutes the train left the station later than planned.
+    pub fn delay(&self) -> u32 {
+        self.actual_departure
+            .map_or(0, |actual| actual.minutes_since(self.scheduled_departure))
+    }
+}
+
+impl SimulationReport {
+    pub fn total_delay(&self) -> u32 {
+        self.rows.iter().map(TrainReport::delay).sum()
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = |time: Option<Time>| time.map_or("--:--".to_string(), |time| time.to_string());
+
+        writeln!(
+            f,
+            "{:<10} {:>9} {:>9} {:>9} {:>9} {:>6}",
+            "train", "sched arr", "arrived", "sched dep", "departed", "delay"
+        )?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<10} {:>9} {:>9} {:>9} {:>9} {:>6}",
+                row.name,
+                row.scheduled_arrival,
+                time(row.actual_arrival),
+                row.scheduled_departure,
+                time(row.actual_departure),
+                row.delay()
+            )?;
+            if let Some(error) = &row.refused {
+                writeln!(f, "{:<10} refused: {error}", "")?;
+            }
+        }
+        write!(f, "total delay: {delay} min", delay = self.total_delay())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{FirstFree, Platform};
+    use crate::train::{FreightTrain, PassengerTrain, TrainKind};
+
+    #[test]
+    fn train_running_twice_gets_a_row_for_each_run() {
+        let mut simulation = Simulation::new(TrainStation::new(1, FirstFree));
+        simulation.schedule(PassengerTrain::new("RB 1".to_string()), Time::at(8, 0), 5);
+        simulation.schedule(PassengerTrain::new("RB 1".to_string()), Time::at(9, 0), 5);
+
+        let report = simulation.run().unwrap();
+
+        let runs: Vec<_> = report
+            .rows
+            .iter()
+            .map(|row| (row.actual_arrival, row.actual_departure))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                (Some(Time::at(8, 0)), Some(Time::at(8, 5))),
+                (Some(Time::at(9, 0)), Some(Time::at(9, 5))),
+            ]
+        );
+    }
+
+    #[test]
+    fn refused_train_is_reported_and_the_others_still_run() {
+        let station = TrainStation::with_platforms(
+            vec![Platform::new(
+                200,
+                &[TrainKind::Passenger, TrainKind::Freight],
+            )],
+            FirstFree,
+        );
+        let mut simulation = Simulation::new(station);
+        simulation.schedule(
+            FreightTrain::new("CT 1".to_string()).with_cars(30),
+            Time::at(8, 0),
+            10,
+        );
+        simulation.schedule(PassengerTrain::new("RB 2".to_string()), Time::at(8, 5), 5);
+
+        let report = simulation.run().unwrap();
+
+        assert!(matches!(
+            report.rows[0].refused,
+            Some(StationError::NoCompatiblePlatform { .. })
+        ));
+        assert_eq!(report.rows[0].actual_arrival, None);
+        assert_eq!(report.rows[1].actual_departure, Some(Time::at(8, 10)));
+        assert!(report
+            .to_string()
+            .contains("refused: 'CT 1' fits no platform"));
+    }
+
+    #[test]
+    fn train_staying_longer_than_the_clock_can_count_still_departs() {
+        let mut simulation = Simulation::new(TrainStation::new(1, FirstFree));
+        simulation.schedule(
+            PassengerTrain::new("RB 1".to_string()),
+            Time::at(8, 0),
+            u32::MAX,
+        );
+
+        let report = simulation.run().unwrap();
+
+        assert_eq!(report.rows[0].actual_departure, Some(Time::at(u32::MAX, 0)));
+        assert_eq!(report.total_delay(), 0);
+    }
+}
@@ -0,0 +1,955 @@
+mod colleague;
+mod invariants;
+mod metrics;
+mod snapshot;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::framework::{Mediator, Message};
+use crate::platform::{FirstFree, Incompatibility, Platform, PlatformAssignment};
+use crate::time::Time;
+use crate::train::{Train, TrainKind};
+
+pub use colleague::{Driver, StationColleague, StationMessage};
+use metrics::MetricsRecorder;
+pub use metrics::StationMetrics;
+pub use snapshot::StationSnapshot;
+
+/// A train reports its arrival. The reply is the platform assigned to it, or
+/// `None` if it has to wait.
+pub struct Arrival<'a>(pub &'a dyn Train);
+
+impl Message for Arrival<'_> {
+    type Reply = Option<usize>;
+}
+
+/// A train reports its departure.
+pub struct Departure<'a>(pub &'a dyn Train);
+
+impl Message for Departure<'_> {
+    type Reply = Result<(), StationError>;
+}
+
+/// Mediator trains report their arrival and departure to.
+pub trait StationMediator: for<'a> Mediator<Arrival<'a>> + for<'a> Mediator<Departure<'a>> {}
+
+impl<T> StationMediator for T where
+    T: for<'a> Mediator<Arrival<'a>> + for<'a> Mediator<Departure<'a>>
+{
+}
+
+/// Waiting line of a station. The train with the highest priority is admitted
+/// first. A train gains one level of priority for every `aging` trains
+/// admitted ahead of it, so freight trains are not starved forever.
+struct ArrivalQueue {
+    waiting: Vec<WaitingTrain>,
+    aging: usize,
+    admissions: usize,
+}
+
+struct WaitingTrain {
+    name: String,
+    priority: u32,
+    // Number of admissions when the train started to wait.
+    since: usize,
+    queued_at: Time,
+}
+
+impl ArrivalQueue {
+    const AGING: usize = 3;
+
+    fn new(aging: usize) -> Self {
+        Self {
+            waiting: Vec::new(),
+            aging: aging.max(1),
+            admissions: 0,
+        }
+    }
+
+    fn push(&mut self, train: &dyn Train, now: Time) {
+        self.waiting.push(WaitingTrain {
+            name: train.name().to_string(),
+            priority: train.priority(),
+            since: self.admissions,
+            queued_at: now,
+        });
+    }
+
+    fn effective_priority(&self, train: &WaitingTrain) -> usize {
+        train.priority as usize + (self.admissions - train.since) / self.aging
+    }
+
+    /// Names of the waiting trains in admission order.
+    fn iter(&self) -> impl Iterator<Item = &str> {
+        let mut order: Vec<&WaitingTrain> = self.waiting.iter().collect();
+        // Stable sort, so trains of equal priority keep their arrival order.
+        order.sort_by_key(|train| std::cmp::Reverse(self.effective_priority(train)));
+        order.into_iter().map(|train| train.name.as_str())
+    }
+
+    /// Takes the train out of the line without admitting it.
+    fn remove(&mut self, name: &str) -> Option<WaitingTrain> {
+        let position = self.waiting.iter().position(|train| train.name == name)?;
+
+        Some(self.waiting.remove(position))
+    }
+
+    /// Takes the train out of the line to move it onto a platform.
+    fn admit(&mut self, name: &str) {
+        if let Some(position) = self.waiting.iter().position(|train| train.name == name) {
+            self.waiting.remove(position);
+            self.admissions += 1;
+        }
+    }
+}
+
+impl Default for ArrivalQueue {
+    fn default() -> Self {
+        Self::new(Self::AGING)
+    }
+}
+
+/// Handle of a train on the station, given out by `accept`. A handle goes stale
+/// once its train has left, even if the slot is reused for another train.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TrainId {
+    index: usize,
+    generation: u32,
+}
+
+impl fmt::Display for TrainId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{index}.{generation}",
+            index = self.index,
+            generation = self.generation
+        )
+    }
+}
+
+/// A train on the station, by handle or by name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrainRef {
+    Id(TrainId),
+    Name(String),
+}
+
+impl From<TrainId> for TrainRef {
+    fn from(id: TrainId) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<String> for TrainRef {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<&str> for TrainRef {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<&String> for TrainRef {
+    fn from(name: &String) -> Self {
+        Self::Name(name.clone())
+    }
+}
+
+/// Handles of the trains on the station. The slot of a train which has left is
+/// reused with the next generation.
+#[derive(Default)]
+struct Handles {
+    slots: Vec<HandleSlot>,
+    free: Vec<usize>,
+    ids: HashMap<String, TrainId>,
+}
+
+struct HandleSlot {
+    generation: u32,
+    train: Option<String>,
+}
+
+impl Handles {
+    fn issue(&mut self, name: &str) -> TrainId {
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(HandleSlot {
+                generation: 0,
+                train: None,
+            });
+            self.slots.len() - 1
+        });
+
+        let slot = &mut self.slots[index];
+        slot.generation += 1;
+        slot.train = Some(name.to_string());
+
+        let id = TrainId {
+            index,
+            generation: slot.generation,
+        };
+        self.ids.insert(name.to_string(), id);
+
+        id
+    }
+
+    fn resolve(&self, id: TrainId) -> Option<&str> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.train.as_deref())
+    }
+
+    fn revoke(&mut self, name: &str) {
+        if let Some(id) = self.ids.remove(name) {
+            self.slots[id.index].train = None;
+            self.free.push(id.index);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StationEvent {
+    Arrived {
+        train: String,
+        kind: TrainKind,
+    },
+    Queued {
+        train: String,
+    },
+    Admitted {
+        train: String,
+        platform: usize,
+    },
+    Departed {
+        train: String,
+        platform: usize,
+    },
+    /// A waiting train left without getting a platform.
+    Withdrawn {
+        train: String,
+    },
+    /// A waiting train was taken off the station for good.
+    Cancelled {
+        train: String,
+    },
+    /// A waiting train was sent on to another station.
+    Diverted {
+        train: String,
+    },
+    /// A train diverted from another station, still waiting since it got
+    /// there.
+    Received {
+        train: String,
+        kind: TrainKind,
+        queued_at: Time,
+    },
+    Rejected {
+        error: StationError,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StationError {
+    DuplicateTrain(String),
+    UnknownTrain(String),
+    /// The train the handle was given out for has left.
+    StaleHandle(TrainId),
+    /// Only waiting trains can be cancelled or diverted.
+    NotWaiting(String),
+    /// The train left the waiting line before it got a platform.
+    Withdrawn(String),
+    /// None of the platforms could ever take the train.
+    NoCompatiblePlatform {
+        train: String,
+        reasons: Vec<(usize, Incompatibility)>,
+    },
+    /// The station bookkeeping contradicts itself.
+    Inconsistency(String),
+}
+
+impl fmt::Display for StationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Arrived { train, .. } => write!(f, "'{train}': Arrived"),
+            Self::Queued { train } => write!(f, "'{train}': Arrival blocked, waiting..."),
+            Self::Admitted { train, platform } => {
+                write!(f, "'{train}': Admitted to platform {platform}")
+            }
+            Self::Departed { train, platform } => {
+                write!(f, "'{train}': Leaving platform {platform}")
+            }
+            Self::Withdrawn { train } => write!(f, "'{train}': Leaving without a platform"),
+            Self::Cancelled { train } => write!(f, "'{train}': Cancelled"),
+            Self::Diverted { train } => write!(f, "'{train}': Diverted to another station"),
+            Self::Received {
+                train, queued_at, ..
+            } => write!(f, "'{train}': Diverted here, waiting since {queued_at}"),
+            Self::Rejected { error } => write!(f, "Rejected: {error}"),
+        }
+    }
+}
+
+impl fmt::Display for StationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateTrain(name) => write!(f, "'{name}' has already arrived"),
+            Self::UnknownTrain(name) => write!(f, "'{name}' is not on the station"),
+            Self::StaleHandle(id) => write!(f, "Train {id} has already left"),
+            Self::NotWaiting(name) => write!(f, "'{name}' is not waiting"),
+            Self::Withdrawn(name) => write!(f, "'{name}' left without a platform"),
+            Self::NoCompatiblePlatform { train, reasons } => {
+                write!(f, "'{train}' fits no platform")?;
+                for (index, (platform, reason)) in reasons.iter().enumerate() {
+                    let separator = if index == 0 { ":" } else { "," };
+                    write!(f, "{separator} platform {platform} {reason}")?;
+                }
+                Ok(())
+            }
+            Self::Inconsistency(message) => write!(f, "Inconsistent station: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for StationError {}
+
+/// Subscriber which keeps the events, so the sequence can be checked afterwards.
+#[derive(Clone, Default)]
+pub struct EventLog(Rc<RefCell<Vec<StationEvent>>>);
+
+impl EventLog {
+    pub fn subscriber(&self) -> impl FnMut(&StationEvent) + 'static {
+        let events = Rc::clone(&self.0);
+        move |event| events.borrow_mut().push(event.clone())
+    }
+
+    pub fn take(&self) -> Vec<StationEvent> {
+        self.0.take()
+    }
+}
+
+type Subscriber = Box<dyn FnMut(&StationEvent)>;
+
+pub struct TrainStation {
+    trains: HashMap<String, Box<dyn Train>>,
+    handles: Handles,
+    train_queue: ArrivalQueue,
+    platforms: Vec<Platform>,
+    assignment: Box<dyn PlatformAssignment>,
+    // Platform a train would like to use, if it is free.
+    preferences: HashMap<String, usize>,
+    departures: usize,
+    now: Time,
+    metrics: MetricsRecorder,
+    subscribers: Vec<Subscriber>,
+    // Whether the invariants are checked after every change.
+    checked: bool,
+}
+
+impl Default for TrainStation {
+    fn default() -> Self {
+        Self::new(1, FirstFree)
+    }
+}
+
+impl TrainStation {
+    pub fn new(platforms: usize, assignment: impl PlatformAssignment + 'static) -> Self {
+        Self::with_platforms(
+            (0..platforms).map(|_| Platform::default()).collect(),
+            assignment,
+        )
+    }
+
+    pub fn with_platforms(
+        platforms: Vec<Platform>,
+        assignment: impl PlatformAssignment + 'static,
+    ) -> Self {
+        Self {
+            trains: HashMap::new(),
+            handles: Handles::default(),
+            train_queue: ArrivalQueue::default(),
+            platforms,
+            assignment: Box::new(assignment),
+            preferences: HashMap::new(),
+            departures: 0,
+            now: Time::default(),
+            metrics: MetricsRecorder::default(),
+            subscribers: Vec::new(),
+            checked: false,
+        }
+    }
+
+    /// Sets after how many admissions a waiting train gains one level of priority.
+    pub fn with_aging(mut self, admissions: usize) -> Self {
+        self.train_queue = ArrivalQueue::new(admissions);
+        self
+    }
+
+    pub fn subscribe(&mut self, subscriber: impl FnMut(&StationEvent) + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
+
+    /// Moves the station clock forward, it never goes back.
+    pub fn advance_to(&mut self, time: Time) {
+        self.now = self.now.max(time);
+    }
+
+    pub fn metrics(&self) -> StationMetrics {
+        self.metrics.metrics(self.now, self.platforms.len())
+    }
+
+    fn emit(&mut self, event: StationEvent) {
+        self.metrics.record(&event, self.now);
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber(&event);
+        }
+    }
+
+    pub fn accept(&mut self, train: impl Train + 'static) -> Result<TrainId, StationError> {
+        self.accept_boxed(Box::new(train))
+    }
+
+    pub fn accept_boxed(&mut self, mut train: Box<dyn Train>) -> Result<TrainId, StationError> {
+        self.check_arrival(train.as_ref())?;
+
+        let id = self.handles.issue(train.name());
+        train.arrive(self);
+        self.trains.insert(train.name().to_string(), train);
+        self.verify()?;
+
+        Ok(id)
+    }
+
+    /// Refuses trains already on the station and trains no platform could
+    /// ever take.
+    pub fn check_arrival(&mut self, train: &dyn Train) -> Result<(), StationError> {
+        if self.trains.contains_key(train.name()) {
+            return Err(self.reject(StationError::DuplicateTrain(train.name().to_string())));
+        }
+
+        match self.check_platforms(train) {
+            Ok(()) => Ok(()),
+            Err(error) => Err(self.reject(error)),
+        }
+    }
+
+    /// Refuses a train none of the platforms could ever take, without
+    /// reporting it to the subscribers. Only the platforms the assignment
+    /// policy or the train's preference could give it count.
+    pub fn check_platforms(&self, train: &dyn Train) -> Result<(), StationError> {
+        let candidates = self.assignment.candidates(train, &self.platforms);
+        let preferred = self.preferences.get(train.name()).copied();
+        let reasons: Vec<(usize, Incompatibility)> = self
+            .platforms
+            .iter()
+            .enumerate()
+            .filter_map(|(index, platform)| {
+                if !candidates.contains(&index) && preferred != Some(index) {
+                    return Some((index, Incompatibility::NotAssigned(train.kind())));
+                }
+                let reason = platform.check(train).err()?;
+                Some((index, reason))
+            })
+            .collect();
+        if reasons.len() == self.platforms.len() {
+            return Err(StationError::NoCompatiblePlatform {
+                train: train.name().to_string(),
+                reasons,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Takes a waiting train off the station for good.
+    pub fn cancel(&mut self, train: impl Into<TrainRef>) -> Result<(), StationError> {
+        let (train, _) = self.take_waiting(train.into())?;
+        self.emit(StationEvent::Cancelled {
+            train: train.name().to_string(),
+        });
+        self.verify()?;
+
+        Ok(())
+    }
+
+    /// Moves a waiting train to the other station. It keeps the priority it
+    /// has gained and the time it started waiting. The train stays here if
+    /// the other station refuses it.
+    pub fn divert(
+        &mut self,
+        train: impl Into<TrainRef>,
+        to: &mut TrainStation,
+    ) -> Result<TrainId, StationError> {
+        let name = match self.waiting_train(train.into()) {
+            Ok(name) => name,
+            Err(error) => return Err(self.reject(error)),
+        };
+        to.check_arrival(self.trains[&name].as_ref())?;
+
+        let (train, waiting) = self.take_waiting(TrainRef::Name(name))?;
+        let priority = self.train_queue.effective_priority(&waiting) as u32;
+        self.emit(StationEvent::Diverted {
+            train: waiting.name.clone(),
+        });
+
+        let id = to.receive(train, priority, waiting.queued_at);
+        self.verify()?;
+        to.verify()?;
+
+        Ok(id)
+    }
+
+    /// Name of the train if it is waiting.
+    fn waiting_train(&self, train: TrainRef) -> Result<String, StationError> {
+        let name = self.resolve(train)?;
+        if !self.trains.contains_key(&name) {
+            return Err(StationError::UnknownTrain(name));
+        }
+        if !self.train_queue.iter().any(|waiting| waiting == name) {
+            return Err(StationError::NotWaiting(name));
+        }
+
+        Ok(name)
+    }
+
+    /// Takes a waiting train off the station without departing it.
+    fn take_waiting(
+        &mut self,
+        train: TrainRef,
+    ) -> Result<(Box<dyn Train>, WaitingTrain), StationError> {
+        let name = match self.waiting_train(train) {
+            Ok(name) => name,
+            Err(error) => return Err(self.reject(error)),
+        };
+
+        let waiting = self
+            .train_queue
+            .remove(&name)
+            .expect("the train is waiting");
+        let train = self
+            .trains
+            .remove(&name)
+            .expect("a waiting train is registered");
+        self.handles.revoke(&name);
+        self.preferences.remove(&name);
+
+        Ok((train, waiting))
+    }
+
+    fn receive(&mut self, train: Box<dyn Train>, priority: u32, queued_at: Time) -> TrainId {
+        let name = train.name().to_string();
+        let id = self.handles.issue(&name);
+        self.emit(StationEvent::Received {
+            train: name.clone(),
+            kind: train.kind(),
+            queued_at,
+        });
+
+        match self.choose_platform(train.as_ref()) {
+            Some(platform) => {
+                self.platforms[platform].train = Some(name.clone());
+                self.emit(StationEvent::Admitted {
+                    train: name.clone(),
+                    platform,
+                });
+            }
+            None => self.train_queue.waiting.push(WaitingTrain {
+                name: name.clone(),
+                priority,
+                since: self.train_queue.admissions,
+                queued_at,
+            }),
+        }
+        self.trains.insert(name, train);
+
+        id
+    }
+
+    pub fn depart(&mut self, train: impl Into<TrainRef>) -> Result<(), StationError> {
+        self.release(train).map(drop)
+    }
+
+    /// Departs the train and hands it over, e.g. to move it to another station.
+    pub fn release(&mut self, train: impl Into<TrainRef>) -> Result<Box<dyn Train>, StationError> {
+        let name = match self.resolve(train.into()) {
+            Ok(name) => name,
+            Err(error) => return Err(self.reject(error)),
+        };
+        let Some(mut train) = self.trains.remove(&name) else {
+            return Err(self.reject(StationError::UnknownTrain(name)));
+        };
+
+        self.handles.revoke(&name);
+        train.depart(self)?;
+        self.verify()?;
+
+        Ok(train)
+    }
+
+    /// The train, if it is on the station.
+    pub fn train(&self, train: impl Into<TrainRef>) -> Result<&dyn Train, StationError> {
+        let name = self.resolve(train.into())?;
+        match self.trains.get(&name) {
+            Some(train) => Ok(train.as_ref()),
+            None => Err(StationError::UnknownTrain(name)),
+        }
+    }
+
+    /// Handle of the train with this name, if it is on the station.
+    pub fn id_of(&self, name: &str) -> Option<TrainId> {
+        self.handles.ids.get(name).copied()
+    }
+
+    fn resolve(&self, train: TrainRef) -> Result<String, StationError> {
+        match train {
+            TrainRef::Id(id) => self
+                .handles
+                .resolve(id)
+                .map(str::to_string)
+                .ok_or(StationError::StaleHandle(id)),
+            TrainRef::Name(name) => Ok(name),
+        }
+    }
+
+    pub fn platform_of(&self, train: &str) -> Option<usize> {
+        self.platforms
+            .iter()
+            .position(|platform| platform.train.as_deref() == Some(train))
+    }
+
+    /// Trains standing on a platform.
+    pub fn occupants(&self) -> impl Iterator<Item = &str> {
+        self.platforms
+            .iter()
+            .filter_map(|platform| platform.train.as_deref())
+    }
+
+    pub fn platforms(&self) -> &[Platform] {
+        &self.platforms
+    }
+
+    /// Names of the waiting trains in the order they will be admitted.
+    pub fn waiting(&self) -> impl Iterator<Item = &str> {
+        self.train_queue.iter()
+    }
+
+    /// Whether the train would get a platform right away.
+    pub fn can_admit(&self, train: &dyn Train) -> bool {
+        self.choose_platform(train).is_some()
+    }
+
+    /// Lets the train use the platform whenever it is free, regardless of the
+    /// assignment policy.
+    pub fn prefer_platform(&mut self, train: &str, platform: usize) {
+        self.preferences.insert(train.to_string(), platform);
+    }
+
+    fn choose_platform(&self, train: &dyn Train) -> Option<usize> {
+        self.preferences
+            .get(train.name())
+            .copied()
+            .filter(|&platform| {
+                self.platforms
+                    .get(platform)
+                    .is_some_and(|candidate| candidate.takes(train))
+            })
+            .or_else(|| self.assignment.assign(train, &self.platforms))
+    }
+
+    fn reject(&mut self, error: StationError) -> StationError {
+        self.emit(StationEvent::Rejected {
+            error: error.clone(),
+        });
+
+        error
+    }
+
+    /// Moves the waiting train with the highest priority which fits a free
+    /// platform onto it.
+    fn admit_next(&mut self) -> Result<(), StationError> {
+        let stale = self
+            .train_queue
+            .iter()
+            .find(|name| !self.trains.contains_key(*name))
+            .map(str::to_string);
+
+        if let Some(name) = stale {
+            // Drop the stale entry, so the queue can move on after the report.
+            // Nobody got a platform, so the other trains do not age.
+            self.train_queue.remove(&name);
+
+            return Err(StationError::Inconsistency(format!(
+                "'{name}' is waiting but not registered"
+            )));
+        }
+
+        let next = self.train_queue.iter().find_map(|name| {
+            let platform = self.choose_platform(self.trains[name].as_ref())?;
+            Some((name.to_string(), platform))
+        });
+
+        if let Some((name, platform)) = next {
+            self.train_queue.admit(&name);
+            self.platforms[platform].train = Some(name.clone());
+            self.emit(StationEvent::Admitted {
+                train: name,
+                platform,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Mediator<Arrival<'_>> for TrainStation {
+    fn request(&mut self, Arrival(train): Arrival) -> Option<usize> {
+        let name = train.name().to_string();
+        self.emit(StationEvent::Arrived {
+            train: name.clone(),
+            kind: train.kind(),
+        });
+
+        match self.choose_platform(train) {
+            Some(platform) => {
+                self.platforms[platform].train = Some(name.clone());
+                self.emit(StationEvent::Admitted {
+                    train: name,
+                    platform,
+                });
+                Some(platform)
+            }
+            None => {
+                self.train_queue.push(train, self.now);
+                self.emit(StationEvent::Queued { train: name });
+                None
+            }
+        }
+    }
+}
+
+impl Mediator<Departure<'_>> for TrainStation {
+    fn request(&mut self, Departure(train): Departure) -> Result<(), StationError> {
+        let name = train.name().to_string();
+        if self.train_queue.remove(&name).is_some() {
+            self.emit(StationEvent::Withdrawn { train: name });
+
+            return Ok(());
+        }
+
+        let Some(platform) = self.platform_of(&name) else {
+            return Err(StationError::Inconsistency(format!(
+                "'{name}' is neither on a platform nor waiting"
+            )));
+        };
+
+        self.departures += 1;
+        self.platforms[platform].train = None;
+        self.platforms[platform].last_departure = self.departures;
+        self.emit(StationEvent::Departed {
+            train: name,
+            platform,
+        });
+
+        self.admit_next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::DedicatedByKind;
+    use crate::train::{FreightTrain, PassengerTrain};
+
+    fn admitted(log: &EventLog) -> Vec<String> {
+        log.take()
+            .into_iter()
+            .filter_map(|event| match event {
+                StationEvent::Admitted { train, .. } => Some(train),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Station with 'CT 1' on its only platform and 'RB 2' waiting.
+    fn busy_station() -> (TrainStation, EventLog) {
+        let mut station = TrainStation::default();
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+
+        station
+            .accept(FreightTrain::new("CT 1".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::new("RB 2".to_string()))
+            .unwrap();
+        log.take();
+
+        (station, log)
+    }
+
+    #[test]
+    fn waiting_train_leaves_without_freeing_the_platform() {
+        let (mut station, log) = busy_station();
+
+        station.depart("RB 2").unwrap();
+
+        assert_eq!(
+            log.take(),
+            [StationEvent::Withdrawn {
+                train: "RB 2".to_string()
+            }]
+        );
+        assert_eq!(station.platform_of("CT 1"), Some(0));
+        assert_eq!(station.waiting().count(), 0);
+    }
+
+    #[test]
+    fn train_on_a_platform_departs_and_the_next_one_is_admitted() {
+        let (mut station, log) = busy_station();
+
+        station.depart("CT 1").unwrap();
+
+        assert_eq!(
+            log.take(),
+            [
+                StationEvent::Departed {
+                    train: "CT 1".to_string(),
+                    platform: 0
+                },
+                StationEvent::Admitted {
+                    train: "RB 2".to_string(),
+                    platform: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_train_cannot_depart() {
+        let (mut station, log) = busy_station();
+
+        let error = StationError::UnknownTrain("IC 3".to_string());
+        assert_eq!(station.depart("IC 3"), Err(error.clone()));
+        assert_eq!(log.take(), [StationEvent::Rejected { error }]);
+        assert_eq!(station.platform_of("CT 1"), Some(0));
+        assert_eq!(station.waiting().collect::<Vec<_>>(), ["RB 2"]);
+    }
+
+    #[test]
+    fn waiting_trains_are_admitted_by_priority() {
+        let mut station = TrainStation::default().with_aging(100);
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+
+        station
+            .accept(FreightTrain::new("CT 1".to_string()))
+            .unwrap();
+        station
+            .accept(FreightTrain::new("CT 2".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::new("RB 3".to_string()))
+            .unwrap();
+        station
+            .accept(FreightTrain::express("CT 4".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::express("IC 5".to_string()))
+            .unwrap();
+
+        for train in ["CT 1", "IC 5", "RB 3", "CT 4", "CT 2"] {
+            station.depart(train).unwrap();
+        }
+
+        assert_eq!(admitted(&log), ["CT 1", "IC 5", "RB 3", "CT 4", "CT 2"]);
+    }
+
+    #[test]
+    fn aging_lets_a_freight_train_overtake_later_passenger_trains() {
+        let mut station = TrainStation::default().with_aging(1);
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+
+        station
+            .accept(FreightTrain::new("train7".to_string()))
+            .unwrap();
+        station
+            .accept(FreightTrain::new("train8".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::new("train9".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::express("train10".to_string()))
+            .unwrap();
+
+        station.depart("train7").unwrap();
+        station
+            .accept(PassengerTrain::new("train11".to_string()))
+            .unwrap();
+        station.depart("train10").unwrap();
+        station.depart("train9").unwrap();
+        // By now 'train8' has gained enough priority while waiting.
+        station
+            .accept(PassengerTrain::new("train12".to_string()))
+            .unwrap();
+        station.depart("train11").unwrap();
+        station.depart("train8").unwrap();
+        station.depart("train12").unwrap();
+
+        assert_eq!(
+            admitted(&log),
+            ["train7", "train10", "train9", "train11", "train8", "train12"]
+        );
+    }
+
+    #[test]
+    fn dropping_a_stale_waiting_entry_does_not_age_the_queue() {
+        let (mut station, _log) = busy_station();
+        let admissions = station.train_queue.admissions;
+        station
+            .train_queue
+            .push(&PassengerTrain::new("IC 3".to_string()), Time::default());
+
+        assert_eq!(
+            station.admit_next(),
+            Err(StationError::Inconsistency(
+                "'IC 3' is waiting but not registered".to_string()
+            ))
+        );
+        assert_eq!(station.train_queue.admissions, admissions);
+        assert_eq!(station.waiting().collect::<Vec<_>>(), ["RB 2"]);
+    }
+
+    #[test]
+    fn train_whose_dedicated_platforms_are_too_short_is_refused() {
+        let platforms = vec![
+            Platform::default(),
+            Platform::new(100, &[TrainKind::Passenger, TrainKind::Freight]),
+        ];
+        let mut station = TrainStation::with_platforms(
+            platforms,
+            DedicatedByKind::new(HashMap::from([(TrainKind::Freight, vec![1])])),
+        );
+
+        let error = station
+            .accept(FreightTrain::new("CT 1".to_string()))
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "'CT 1' fits no platform: platform 0 is not assigned to freight trains, \
+             platform 1 is 100 m long but the train is 320 m with 20 cars"
+        );
+        assert_eq!(station.waiting().count(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use super::{EventLog, StationError, StationEvent, TrainStation};
+use crate::framework::{Colleague, ColleagueId, Envelope, Hub, Message};
+use crate::train::Train;
+
+/// Messages between a station and the drivers of trains on a `Hub`.
+pub enum StationMessage {
+    Arrive(Box<dyn Train>),
+    Depart(String),
+    /// Sent by the station to the colleague which brought the train in.
+    Admitted {
+        train: String,
+        platform: usize,
+    },
+}
+
+impl Message for StationMessage {
+    type Reply = Result<(), StationError>;
+}
+
+/// Station registered with a hub. The colleague which brings a train in is
+/// told its platform by a posted message, which may be long after the arrival
+/// if the train has to wait.
+pub struct StationColleague {
+    station: TrainStation,
+    log: EventLog,
+    drivers: HashMap<String, ColleagueId>,
+}
+
+impl StationColleague {
+    pub fn new(mut station: TrainStation) -> Self {
+        let log = EventLog::default();
+        station.subscribe(log.subscriber());
+
+        Self {
+            station,
+            log,
+            drivers: HashMap::new(),
+        }
+    }
+}
+
+impl Colleague<StationMessage> for StationColleague {
+    fn receive(
+        &mut self,
+        envelope: Envelope<StationMessage>,
+        hub: &mut Hub<StationMessage>,
+    ) -> Result<(), StationError> {
+        let result = match envelope.message {
+            StationMessage::Arrive(train) => {
+                let name = train.name().to_string();
+                self.station.accept_boxed(train).map(|_| {
+                    self.drivers.insert(name, envelope.from);
+                })
+            }
+            StationMessage::Depart(train) => self.station.depart(&train).map(|()| {
+                self.drivers.remove(&train);
+            }),
+            StationMessage::Admitted { .. } => Ok(()),
+        };
+
+        for event in self.log.take() {
+            if let StationEvent::Admitted { train, platform } = event {
+                if let Some(&driver) = self.drivers.get(&train) {
+                    // A driver which has left the hub is not told.
+                    let _ = hub.post(
+                        envelope.to,
+                        driver,
+                        StationMessage::Admitted { train, platform },
+                    );
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Driver waiting to be told the platform of their train.
+pub struct Driver;
+
+impl Colleague<StationMessage> for Driver {
+    fn receive(
+        &mut self,
+        envelope: Envelope<StationMessage>,
+        _hub: &mut Hub<StationMessage>,
+    ) -> Result<(), StationError> {
+        if let StationMessage::Admitted { train, platform } = envelope.message {
+            println!(
+                "Driver {me}: '{train}' may go to platform {platform}",
+                me = envelope.to
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::platform::FirstFree;
+    use crate::train::{FreightTrain, PassengerTrain};
+
+    /// Driver which writes down the platforms it is told.
+    struct Notebook(Rc<RefCell<Vec<(String, usize)>>>);
+
+    impl Colleague<StationMessage> for Notebook {
+        fn receive(
+            &mut self,
+            envelope: Envelope<StationMessage>,
+            _hub: &mut Hub<StationMessage>,
+        ) -> Result<(), StationError> {
+            if let StationMessage::Admitted { train, platform } = envelope.message {
+                self.0.borrow_mut().push((train, platform));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn driver_is_told_the_platform_once_the_train_gets_one() {
+        let mut hub = Hub::default();
+        let station = hub.register(StationColleague::new(TrainStation::new(1, FirstFree)));
+        let first = Rc::new(RefCell::new(Vec::new()));
+        let second = Rc::new(RefCell::new(Vec::new()));
+        let drivers = [
+            hub.register(Notebook(Rc::clone(&first))),
+            hub.register(Notebook(Rc::clone(&second))),
+        ];
+
+        let train = PassengerTrain::new("RB 1".to_string());
+        hub.post(drivers[0], station, StationMessage::Arrive(Box::new(train)))
+            .unwrap();
+        let train = FreightTrain::new("CT 2".to_string());
+        hub.post(drivers[1], station, StationMessage::Arrive(Box::new(train)))
+            .unwrap();
+        hub.run().unwrap();
+
+        assert_eq!(*first.borrow(), [("RB 1".to_string(), 0)]);
+        assert!(second.borrow().is_empty());
+
+        let depart = StationMessage::Depart("RB 1".to_string());
+        assert_eq!(hub.request(drivers[0], station, depart), Ok(Ok(())));
+        hub.run().unwrap();
+
+        assert_eq!(*second.borrow(), [("CT 2".to_string(), 0)]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use super::{StationError, TrainStation};
+
+impl TrainStation {
+    /// Checks the station after every accept, departure, cancellation and
+    /// diversion, which then fail with `StationError::Inconsistency`.
+    pub fn with_invariant_checks(mut self) -> Self {
+        self.checked = true;
+        self
+    }
+
+    /// Checks that the bookkeeping agrees with itself: every train is either
+    /// on exactly one platform or waiting, and only registered trains are on
+    /// a platform, waiting or holding a handle.
+    pub fn check_invariants(&self) -> Result<(), StationError> {
+        let fail = |message: String| Err(StationError::Inconsistency(message));
+
+        let mut placed = HashSet::new();
+        for name in self.occupants() {
+            if !self.trains.contains_key(name) {
+                return fail(format!("'{name}' is on a platform but not registered"));
+            }
+            if !placed.insert(name) {
+                return fail(format!("'{name}' is on more than one platform"));
+            }
+        }
+
+        for name in self.train_queue.iter() {
+            if !self.trains.contains_key(name) {
+                return fail(format!("'{name}' is waiting but not registered"));
+            }
+            if !placed.insert(name) {
+                return fail(format!("'{name}' is on a platform and waiting"));
+            }
+        }
+
+        if let Some(name) = self
+            .trains
+            .keys()
+            .find(|name| !placed.contains(name.as_str()))
+        {
+            return fail(format!("'{name}' is neither on a platform nor waiting"));
+        }
+
+        for (name, &id) in &self.handles.ids {
+            if !self.trains.contains_key(name) {
+                return fail(format!("'{name}' has a handle but is not registered"));
+            }
+            if self.handles.resolve(id) != Some(name.as_str()) {
+                return fail(format!("handle {id} of '{name}' refers to another train"));
+            }
+        }
+        if self.handles.ids.len() != self.trains.len() {
+            return fail("some trains have no handle".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn verify(&self) -> Result<(), StationError> {
+        if self.checked {
+            self.check_invariants()
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fmt::Write;
+
+use super::snapshot::MetricsSnapshot;
+use super::StationEvent;
+use crate::time::Time;
+use crate::train::TrainKind;
+
+/// Figures of a station run, from its first event until now.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StationMetrics {
+    /// Minutes of station time covered.
+    pub elapsed: u32,
+    pub waits: BTreeMap<TrainKind, WaitMetrics>,
+    /// Share of the elapsed time each platform was occupied, in percent.
+    pub occupancy: Vec<f64>,
+    pub departures: usize,
+    pub throughput_per_hour: f64,
+    /// Arrivals which had to wait for a platform.
+    pub blocked_arrivals: usize,
+}
+
+/// Minutes admitted trains of a kind spent waiting for a platform.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WaitMetrics {
+    pub trains: usize,
+    pub average: f64,
+    pub max: u32,
+}
+
+impl StationMetrics {
+    pub fn to_csv(&self) -> String {
+        let mut csv = "metric,subject,value\n".to_string();
+        let mut row = |metric: &str, subject: &str, value: String| {
+            // Writing to a `String` cannot fail.
+            let _ = writeln!(csv, "{metric},{subject},{value}");
+        };
+
+        row("elapsed_min", "station", self.elapsed.to_string());
+        for (kind, wait) in &self.waits {
+            let kind = kind.to_string();
+            row("admitted_trains", &kind, wait.trains.to_string());
+            row("average_wait_min", &kind, format!("{:.2}", wait.average));
+            row("max_wait_min", &kind, wait.max.to_string());
+        }
+        for (platform, occupancy) in self.occupancy.iter().enumerate() {
+            row(
+                "occupancy_percent",
+                &format!("platform {platform}"),
+                format!("{occupancy:.1}"),
+            );
+        }
+        row("departures", "station", self.departures.to_string());
+        row(
+            "throughput_per_hour",
+            "station",
+            format!("{:.2}", self.throughput_per_hour),
+        );
+        row(
+            "blocked_arrivals",
+            "station",
+            self.blocked_arrivals.to_string(),
+        );
+
+        csv
+    }
+}
+
+impl fmt::Display for StationMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "elapsed: {elapsed} min", elapsed = self.elapsed)?;
+        for (kind, wait) in &self.waits {
+            writeln!(
+                f,
+                "{kind} trains: {trains} admitted, wait {average:.1} min on average, {max} min at most",
+                trains = wait.trains,
+                average = wait.average,
+                max = wait.max
+            )?;
+        }
+        for (platform, occupancy) in self.occupancy.iter().enumerate() {
+            writeln!(
+                f,
+                "platform {platform}: occupied {occupancy:.1}% of the time"
+            )?;
+        }
+        writeln!(
+            f,
+            "throughput: {departures} departures, {throughput:.2} per hour",
+            departures = self.departures,
+            throughput = self.throughput_per_hour
+        )?;
+        write!(
+            f,
+            "blocked arrivals: {blocked}",
+            blocked = self.blocked_arrivals
+        )
+    }
+}
+
+/// Keeps what the metrics need from the station events.
+#[derive(Default)]
+pub(super) struct MetricsRecorder {
+    started: Option<Time>,
+    kinds: HashMap<String, TrainKind>,
+    queued_at: HashMap<String, Time>,
+    // Per kind: admitted trains, total and longest wait.
+    waits: BTreeMap<TrainKind, (usize, u32, u32)>,
+    occupied_since: HashMap<usize, Time>,
+    occupied_minutes: HashMap<usize, u32>,
+    departures: usize,
+    blocked_arrivals: usize,
+}
+
+impl MetricsRecorder {
+    pub(super) fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            started: self.started,
+            kinds: self
+                .kinds
+                .iter()
+                .map(|(train, kind)| (train.clone(), *kind))
+                .collect(),
+            queued_at: self
+                .queued_at
+                .iter()
+                .map(|(train, since)| (train.clone(), *since))
+                .collect(),
+            waits: self.waits.clone(),
+            occupied_since: self
+                .occupied_since
+                .iter()
+                .map(|(platform, since)| (*platform, *since))
+                .collect(),
+            occupied_minutes: self
+                .occupied_minutes
+                .iter()
+                .map(|(platform, minutes)| (*platform, *minutes))
+                .collect(),
+            departures: self.departures,
+            blocked_arrivals: self.blocked_arrivals,
+        }
+    }
+
+    pub(super) fn restore(snapshot: MetricsSnapshot) -> Self {
+        Self {
+            started: snapshot.started,
+            kinds: snapshot.kinds.into_iter().collect(),
+            queued_at: snapshot.queued_at.into_iter().collect(),
+            waits: snapshot.waits,
+            occupied_since: snapshot.occupied_since.into_iter().collect(),
+            occupied_minutes: snapshot.occupied_minutes.into_iter().collect(),
+            departures: snapshot.departures,
+            blocked_arrivals: snapshot.blocked_arrivals,
+        }
+    }
+
+    pub(super) fn record(&mut self, event: &StationEvent, now: Time) {
+        self.started.get_or_insert(now);
+
+        match event {
+            StationEvent::Arrived { train, kind } => {
+                self.kinds.insert(train.clone(), *kind);
+            }
+            StationEvent::Queued { train } => {
+                self.blocked_arrivals += 1;
+                self.queued_at.insert(train.clone(), now);
+            }
+            StationEvent::Admitted { train, platform } => {
+                let wait = self
+                    .queued_at
+                    .remove(train)
+                    .map_or(0, |since| now.minutes_since(since));
+                if let Some(kind) = self.kinds.get(train) {
+                    let (trains, total, max) = self.waits.entry(*kind).or_default();
+                    *trains += 1;
+                    *total += wait;
+                    *max = (*max).max(wait);
+                }
+                self.occupied_since.insert(*platform, now);
+            }
+            StationEvent::Departed { train, platform } => {
+                self.departures += 1;
+                if let Some(since) = self.occupied_since.remove(platform) {
+                    *self.occupied_minutes.entry(*platform).or_default() +=
+                        now.minutes_since(since);
+                }
+                self.kinds.remove(train);
+            }
+            StationEvent::Received {
+                train,
+                kind,
+                queued_at,
+            } => {
+                self.kinds.insert(train.clone(), *kind);
+                self.queued_at.insert(train.clone(), *queued_at);
+            }
+            StationEvent::Withdrawn { train }
+            | StationEvent::Cancelled { train }
+            | StationEvent::Diverted { train } => {
+                self.queued_at.remove(train);
+                self.kinds.remove(train);
+            }
+            StationEvent::Rejected { .. } => (),
+        }
+    }
+
+    pub(super) fn metrics(&self, now: Time, platforms: usize) -> StationMetrics {
+        let elapsed = self.started.map_or(0, |started| now.minutes_since(started));
+        let share = |minutes: u32, of: u32| {
+            if of == 0 {
+                0.0
+            } else {
+                f64::from(minutes) / f64::from(of)
+            }
+        };
+
+        StationMetrics {
+            elapsed,
+            waits: self
+                .waits
+                .iter()
+                .map(|(kind, &(trains, total, max))| {
+                    let average = share(total, trains as u32);
+                    (
+                        *kind,
+                        WaitMetrics {
+                            trains,
+                            average,
+                            max,
+                        },
+                    )
+                })
+                .collect(),
+            occupancy: (0..platforms)
+                .map(|platform| {
+                    let finished = self.occupied_minutes.get(&platform).copied();
+                    let ongoing = self
+                        .occupied_since
+                        .get(&platform)
+                        .map(|since| now.minutes_since(*since));
+                    let occupied = finished.unwrap_or(0) + ongoing.unwrap_or(0);
+                    100.0 * share(occupied, elapsed)
+                })
+                .collect(),
+            departures: self.departures,
+            throughput_per_hour: 60.0 * share(self.departures as u32, elapsed),
+            blocked_arrivals: self.blocked_arrivals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::FirstFree;
+    use crate::station::{StationSnapshot, TrainStation};
+    use crate::time::Time;
+    use crate::train::{FreightTrain, PassengerTrain, TrainKind};
+
+    #[test]
+    fn restored_station_keeps_its_metrics() {
+        let mut station = TrainStation::new(1, FirstFree);
+        station.advance_to(Time::at(8, 0));
+        station
+            .accept(FreightTrain::new("CT 1".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::new("RB 2".to_string()))
+            .unwrap();
+        station.advance_to(Time::at(8, 10));
+
+        let json = station.snapshot().to_json();
+        let mut station =
+            TrainStation::restore(StationSnapshot::from_json(&json).unwrap(), FirstFree).unwrap();
+        station.advance_to(Time::at(8, 20));
+        station.depart("CT 1").unwrap();
+        let metrics = station.metrics();
+
+        assert_eq!(metrics.elapsed, 20);
+        assert_eq!(metrics.occupancy, [100.0]);
+        assert_eq!(metrics.waits[&TrainKind::Passenger].max, 20);
+        assert_eq!(metrics.blocked_arrivals, 1);
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ArrivalQueue, HandleSlot, Handles, MetricsRecorder, StationError, TrainId, TrainStation,
+    WaitingTrain,
+};
+use crate::platform::{Platform, PlatformAssignment};
+use crate::time::Time;
+use crate::train::{TrainKind, TrainSpec};
+
+/// Full state of a `TrainStation` except its assignment policy and
+/// subscribers, which are given again on restore.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationSnapshot {
+    pub trains: Vec<TrainSpec>,
+    pub platforms: Vec<PlatformSnapshot>,
+    pub queue: QueueSnapshot,
+    pub preferences: BTreeMap<String, usize>,
+    pub departures: usize,
+    #[serde(default)]
+    pub now: Time,
+    /// Older snapshots have none, their metrics start afresh.
+    #[serde(default)]
+    pub metrics: MetricsSnapshot,
+    /// Older snapshots have none, their trains get new handles.
+    #[serde(default)]
+    pub handles: HandlesSnapshot,
+}
+
+/// Handle slots, so that handles given out before the save still refer to
+/// the same trains after a restore.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandlesSnapshot {
+    pub slots: Vec<HandleSlotSnapshot>,
+    /// Slots to reuse, the last one first.
+    pub free: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandleSlotSnapshot {
+    pub generation: u32,
+    pub train: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlatformSnapshot {
+    pub train: Option<String>,
+    pub last_departure: usize,
+    pub length: u32,
+    pub kinds: Vec<TrainKind>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueSnapshot {
+    /// In the order the trains started to wait.
+    pub waiting: Vec<WaitingSnapshot>,
+    pub aging: usize,
+    pub admissions: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WaitingSnapshot {
+    pub name: String,
+    pub priority: u32,
+    pub since: usize,
+    #[serde(default)]
+    pub queued_at: Time,
+}
+
+/// What the station has recorded for its metrics so far.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub started: Option<Time>,
+    pub kinds: BTreeMap<String, TrainKind>,
+    pub queued_at: BTreeMap<String, Time>,
+    /// Per kind: admitted trains, total and longest wait.
+    pub waits: BTreeMap<TrainKind, (usize, u32, u32)>,
+    pub occupied_since: BTreeMap<usize, Time>,
+    pub occupied_minutes: BTreeMap<usize, u32>,
+    pub departures: usize,
+    pub blocked_arrivals: usize,
+}
+
+impl StationSnapshot {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a snapshot is always valid JSON")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl TrainStation {
+    pub fn snapshot(&self) -> StationSnapshot {
+        let mut trains: Vec<TrainSpec> = self
+            .trains
+            .values()
+            .map(|train| TrainSpec::of(train.as_ref()))
+            .collect();
+        trains.sort_by(|a, b| a.name.cmp(&b.name));
+
+        StationSnapshot {
+            trains,
+            platforms: self
+                .platforms
+                .iter()
+                .map(|platform| PlatformSnapshot {
+                    train: platform.train.clone(),
+                    last_departure: platform.last_departure,
+                    length: platform.length,
+                    kinds: platform.kinds.clone(),
+                })
+                .collect(),
+            queue: QueueSnapshot {
+                waiting: self
+                    .train_queue
+                    .waiting
+                    .iter()
+                    .map(|train| WaitingSnapshot {
+                        name: train.name.clone(),
+                        priority: train.priority,
+                        since: train.since,
+                        queued_at: train.queued_at,
+                    })
+                    .collect(),
+                aging: self.train_queue.aging,
+                admissions: self.train_queue.admissions,
+            },
+            preferences: self
+                .preferences
+                .iter()
+                .map(|(train, platform)| (train.clone(), *platform))
+                .collect(),
+            departures: self.departures,
+            now: self.now,
+            metrics: self.metrics.snapshot(),
+            handles: HandlesSnapshot {
+                slots: self
+                    .handles
+                    .slots
+                    .iter()
+                    .map(|slot| HandleSlotSnapshot {
+                        generation: slot.generation,
+                        train: slot.train.clone(),
+                    })
+                    .collect(),
+                free: self.handles.free.clone(),
+            },
+        }
+    }
+
+    /// Rebuilds the station, refusing snapshots which break the station
+    /// invariants, e.g. refer to trains the station does not know.
+    pub fn restore(
+        snapshot: StationSnapshot,
+        assignment: impl PlatformAssignment + 'static,
+    ) -> Result<Self, StationError> {
+        let trains: HashMap<_, _> = snapshot
+            .trains
+            .iter()
+            .map(|spec| (spec.name.clone(), spec.build()))
+            .collect();
+
+        let platforms = snapshot
+            .platforms
+            .into_iter()
+            .map(|platform| Platform {
+                train: platform.train,
+                last_departure: platform.last_departure,
+                ..Platform::new(platform.length, &platform.kinds)
+            })
+            .collect();
+
+        let mut station = Self::with_platforms(platforms, assignment);
+        station.trains = trains;
+        if snapshot.handles.slots.is_empty() {
+            for spec in &snapshot.trains {
+                station.handles.issue(&spec.name);
+            }
+        } else {
+            station.handles = Handles::restore(snapshot.handles)?;
+        }
+        station.train_queue = ArrivalQueue {
+            waiting: snapshot
+                .queue
+                .waiting
+                .into_iter()
+                .map(|train| WaitingTrain {
+                    name: train.name,
+                    priority: train.priority,
+                    since: train.since,
+                    queued_at: train.queued_at,
+                })
+                .collect(),
+            aging: snapshot.queue.aging.max(1),
+            admissions: snapshot.queue.admissions,
+        };
+        station.preferences = snapshot.preferences.into_iter().collect();
+        station.departures = snapshot.departures;
+        station.now = snapshot.now;
+        station.metrics = MetricsRecorder::restore(snapshot.metrics);
+        station.check_restored()?;
+
+        Ok(station)
+    }
+
+    /// Checks what a snapshot can get wrong beyond the station invariants.
+    fn check_restored(&self) -> Result<(), StationError> {
+        let fail = |message: String| Err(StationError::Inconsistency(message));
+
+        let admissions = self.train_queue.admissions;
+        if let Some(train) = self
+            .train_queue
+            .waiting
+            .iter()
+            .find(|train| train.since > admissions)
+        {
+            return fail(format!(
+                "'{name}' started to wait after {since} admissions, but there were only {admissions}",
+                name = train.name,
+                since = train.since
+            ));
+        }
+
+        self.check_invariants()?;
+
+        if let Some((train, platform)) = self
+            .preferences
+            .iter()
+            .find(|(_, &platform)| platform >= self.platforms.len())
+        {
+            return fail(format!(
+                "'{train}' prefers platform {platform}, which does not exist"
+            ));
+        }
+
+        for (index, platform) in self.platforms.iter().enumerate() {
+            let Some(name) = &platform.train else {
+                continue;
+            };
+            if let Err(reason) = platform.check(self.trains[name].as_ref()) {
+                return fail(format!("'{name}' is on platform {index}, which {reason}"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handles {
+    fn restore(snapshot: HandlesSnapshot) -> Result<Self, StationError> {
+        if let Some(index) = snapshot.free.iter().find(|&&index| {
+            snapshot
+                .slots
+                .get(index)
+                .is_none_or(|slot| slot.train.is_some())
+        }) {
+            return Err(StationError::Inconsistency(format!(
+                "handle slot {index} is free but not empty"
+            )));
+        }
+
+        let ids = snapshot
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let id = TrainId {
+                    index,
+                    generation: slot.generation,
+                };
+                Some((slot.train.clone()?, id))
+            })
+            .collect();
+
+        Ok(Self {
+            slots: snapshot
+                .slots
+                .into_iter()
+                .map(|slot| HandleSlot {
+                    generation: slot.generation,
+                    train: slot.train,
+                })
+                .collect(),
+            free: snapshot.free,
+            ids,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::FirstFree;
+    use crate::train::{FreightTrain, PassengerTrain};
+
+    /// Snapshot of a station with 'CT 1' on platform 0 and 'RB 2' on
+    /// platform 1.
+    fn snapshot() -> StationSnapshot {
+        let mut station = TrainStation::new(2, FirstFree);
+        station
+            .accept(FreightTrain::new("CT 1".to_string()))
+            .unwrap();
+        station
+            .accept(PassengerTrain::new("RB 2".to_string()))
+            .unwrap();
+
+        station.snapshot()
+    }
+
+    fn restore(snapshot: StationSnapshot) -> Result<TrainStation, StationError> {
+        TrainStation::restore(snapshot, FirstFree)
+    }
+
+    #[test]
+    fn consistent_snapshot_is_restored() {
+        let snapshot = snapshot();
+
+        assert_eq!(restore(snapshot.clone()).unwrap().snapshot(), snapshot);
+    }
+
+    #[test]
+    fn handles_from_before_the_save_keep_their_train() {
+        let mut station = TrainStation::new(2, FirstFree);
+        let a = station.accept(FreightTrain::new("a".to_string())).unwrap();
+        let b = station.accept(FreightTrain::new("b".to_string())).unwrap();
+        station.depart(a).unwrap();
+
+        let mut station = restore(station.snapshot()).unwrap();
+
+        assert_eq!(station.train(b).unwrap().name(), "b");
+        assert_eq!(station.train(a).err(), Some(StationError::StaleHandle(a)));
+        let c = station.accept(FreightTrain::new("c".to_string())).unwrap();
+        assert_ne!(c, a);
+        assert_eq!(station.train(c).unwrap().name(), "c");
+    }
+
+    #[test]
+    fn snapshot_with_a_taken_slot_marked_free_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.handles.free.push(0);
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_a_train_on_two_platforms_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.platforms[1].train = Some("CT 1".to_string());
+        snapshot.trains.retain(|train| train.name != "RB 2");
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_a_train_on_a_platform_and_waiting_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.queue.waiting.push(WaitingSnapshot {
+            name: "RB 2".to_string(),
+            priority: 2,
+            since: 0,
+            queued_at: Time::default(),
+        });
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_a_train_which_is_nowhere_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.platforms[1].train = None;
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_an_unknown_train_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.trains.retain(|train| train.name != "RB 2");
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_with_a_train_waiting_since_a_later_admission_is_refused() {
+        let mut station = TrainStation::new(1, FirstFree);
+        for name in ["CT 1", "RB 2", "RB 3"] {
+            station
+                .accept(PassengerTrain::new(name.to_string()))
+                .unwrap();
+        }
+        let mut snapshot = station.snapshot();
+        snapshot.queue.waiting[1].since = snapshot.queue.admissions + 1;
+
+        assert!(matches!(
+            restore(snapshot),
+            Err(StationError::Inconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn snapshot_preferring_a_platform_which_does_not_exist_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.preferences.insert("RB 2".to_string(), 2);
+
+        assert_eq!(
+            restore(snapshot).err(),
+            Some(StationError::Inconsistency(
+                "'RB 2' prefers platform 2, which does not exist".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn snapshot_with_a_train_on_a_platform_it_does_not_fit_is_refused() {
+        let mut snapshot = snapshot();
+        snapshot.platforms[0].kinds = vec![TrainKind::Passenger];
+
+        assert_eq!(
+            restore(snapshot).err(),
+            Some(StationError::Inconsistency(
+                "'CT 1' is on platform 0, which takes no freight trains".to_string()
+            ))
+        );
+    }
+}
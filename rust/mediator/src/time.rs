@@ -0,0 +1,47 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Time of the simulated day, in minutes since midnight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Time(u32);
+
+impl Time {
+    /// Times too far ahead to count in minutes are clamped to the latest one.
+    pub fn at(hours: u32, minutes: u32) -> Self {
+        Self(hours.saturating_mul(60).saturating_add(minutes))
+    }
+
+    pub fn after(self, minutes: u32) -> Self {
+        Self(self.0.saturating_add(minutes))
+    }
+
+    pub fn minutes_since(self, earlier: Time) -> u32 {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+impl FromStr for Time {
+    type Err = String;
+
+    /// Parses `HH:MM`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{text}' is not a time of day (HH:MM)");
+        let (hours, minutes) = text.split_once(':').ok_or_else(invalid)?;
+        let hours: u32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+
+        Ok(Self::at(hours, minutes))
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(&format!("{:02}:{:02}", self.0 / 60, self.0 % 60))
+    }
+}
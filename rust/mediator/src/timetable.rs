@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::time::Time;
+use crate::train::{FreightTrain, PassengerTrain, Train};
+
+type Factory = Box<dyn Fn(String) -> Box<dyn Train>>;
+
+/// Kinds of trains a timetable can refer to by name.
+pub struct TrainRegistry {
+    factories: HashMap<String, Factory>,
+}
+
+impl TrainRegistry {
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, kind: &str, factory: impl Fn(String) -> Box<dyn Train> + 'static) {
+        self.factories.insert(kind.to_string(), Box::new(factory));
+    }
+
+    pub fn build(&self, kind: &str, name: String) -> Option<Box<dyn Train>> {
+        self.factories.get(kind).map(|factory| factory(name))
+    }
+}
+
+impl Default for TrainRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("passenger", |name| Box::new(PassengerTrain::new(name)));
+        registry.register("passenger-express", |name| {
+            Box::new(PassengerTrain::express(name))
+        });
+        registry.register("freight", |name| Box::new(FreightTrain::new(name)));
+        registry.register("freight-express", |name| {
+            Box::new(FreightTrain::express(name))
+        });
+        registry
+    }
+}
+
+pub struct TimetableEntry {
+    pub train: Box<dyn Train>,
+    pub arrival: Time,
+    pub departure: Time,
+    pub platform: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum TimetableError {
+    Io(io::Error),
+    UnsupportedFormat(String),
+    Parse {
+        line: usize,
+        field: Option<&'static str>,
+        message: String,
+    },
+}
+
+impl fmt::Display for TimetableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Cannot read timetable: {error}"),
+            Self::UnsupportedFormat(path) => {
+                write!(f, "'{path}' is neither a .csv nor a .toml timetable")
+            }
+            Self::Parse {
+                line,
+                field: Some(field),
+                message,
+            } => write!(f, "line {line}, field '{field}': {message}"),
+            Self::Parse {
+                line,
+                field: None,
+                message,
+            } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TimetableError {}
+
+impl From<io::Error> for TimetableError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Loads a timetable, the format is picked by the file extension.
+pub fn load(path: &Path, registry: &TrainRegistry) -> Result<Vec<TimetableEntry>, TimetableError> {
+    let text = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => from_csv(&text, registry),
+        Some("toml") => from_toml(&text, registry),
+        _ => Err(TimetableError::UnsupportedFormat(
+            path.display().to_string(),
+        )),
+    }
+}
+
+/// Reads rows of `name,kind,arrival,departure,platform` with a header line.
+/// The platform column is optional and may be left empty.
+pub fn from_csv(
+    text: &str,
+    registry: &TrainRegistry,
+) -> Result<Vec<TimetableEntry>, TimetableError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |field: &'static str| {
+        headers
+            .iter()
+            .position(|header| header == field)
+            .ok_or(TimetableError::Parse {
+                line: 1,
+                field: Some(field),
+                message: "missing column".to_string(),
+            })
+    };
+    let columns = [
+        column("name")?,
+        column("kind")?,
+        column("arrival")?,
+        column("departure")?,
+    ];
+    let platform = headers.iter().position(|header| header == "platform");
+
+    let mut entries = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(csv_error)?;
+        let line = row
+            .position()
+            .map_or(0, |position| position.line() as usize);
+        let field = |column: usize| Field {
+            value: row.get(column).unwrap_or_default().to_string(),
+            line,
+        };
+
+        let [name, kind, arrival, departure] = columns.map(field);
+        let platform = platform
+            .map(field)
+            .filter(|platform| !platform.value.is_empty());
+
+        entries.push(
+            Record {
+                name,
+                kind,
+                arrival,
+                departure,
+                platform,
+            }
+            .into_entry(registry)?,
+        );
+    }
+
+    Ok(entries)
+}
+
+fn csv_error(error: csv::Error) -> TimetableError {
+    TimetableError::Parse {
+        line: error
+            .position()
+            .map_or(0, |position| position.line() as usize),
+        field: None,
+        message: error.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+struct TomlTimetable {
+    #[serde(default)]
+    train: Vec<TomlTrain>,
+}
+
+#[derive(Deserialize)]
+struct TomlTrain {
+    name: Spanned<String>,
+    kind: Spanned<String>,
+    arrival: Spanned<String>,
+    departure: Spanned<String>,
+    platform: Option<Spanned<i64>>,
+}
+
+/// Reads a `[[train]]` table per train with the same fields as the CSV format.
+pub fn from_toml(
+    text: &str,
+    registry: &TrainRegistry,
+) -> Result<Vec<TimetableEntry>, TimetableError> {
+    let line_at = |offset: usize| text[..offset].matches('\n').count() + 1;
+    let field = |value: Spanned<String>| Field {
+        line: line_at(value.span().start),
+        value: value.into_inner(),
+    };
+
+    let timetable: TomlTimetable = toml::from_str(text).map_err(|error| TimetableError::Parse {
+        line: error.span().map_or(0, |span| line_at(span.start)),
+        field: None,
+        message: error.message().to_string(),
+    })?;
+
+    timetable
+        .train
+        .into_iter()
+        .map(|train| {
+            Record {
+                name: field(train.name),
+                kind: field(train.kind),
+                arrival: field(train.arrival),
+                departure: field(train.departure),
+                platform: train.platform.map(|platform| Field {
+                    line: line_at(platform.span().start),
+                    value: platform.into_inner().to_string(),
+                }),
+            }
+            .into_entry(registry)
+        })
+        .collect()
+}
+
+// Raw text of a timetable row, before it is checked.
+struct Record {
+    name: Field,
+    kind: Field,
+    arrival: Field,
+    departure: Field,
+    platform: Option<Field>,
+}
+
+struct Field {
+    value: String,
+    line: usize,
+}
+
+impl Field {
+    fn error(&self, field: &'static str, message: String) -> TimetableError {
+        TimetableError::Parse {
+            line: self.line,
+            field: Some(field),
+            message,
+        }
+    }
+
+    fn time(&self, field: &'static str) -> Result<Time, TimetableError> {
+        self.value
+            .parse()
+            .map_err(|message| self.error(field, message))
+    }
+}
+
+impl Record {
+    fn into_entry(self, registry: &TrainRegistry) -> Result<TimetableEntry, TimetableError> {
+        if self.name.value.is_empty() {
+            return Err(self.name.error("name", "train name is empty".to_string()));
+        }
+
+        let arrival = self.arrival.time("arrival")?;
+        let departure = self.departure.time("departure")?;
+        if departure < arrival {
+            return Err(self.departure.error(
+                "departure",
+                format!("departs at {departure} before arriving at {arrival}"),
+            ));
+        }
+
+        let platform = match &self.platform {
+            Some(platform) => Some(platform.value.parse().map_err(|_| {
+                platform.error(
+                    "platform",
+                    format!("'{value}' is not a platform number", value = platform.value),
+                )
+            })?),
+            None => None,
+        };
+
+        let train = registry
+            .build(&self.kind.value, self.name.value)
+            .ok_or_else(|| {
+                self.kind.error(
+                    "kind",
+                    format!("unknown kind of train '{kind}'", kind = self.kind.value),
+                )
+            })?;
+
+        Ok(TimetableEntry {
+            train,
+            arrival,
+            departure,
+            platform,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(result: Result<Vec<TimetableEntry>, TimetableError>) -> TimetableError {
+        match result {
+            Ok(_) => panic!("the timetable was accepted"),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn csv_time_which_is_not_a_time_of_day_is_reported_with_its_line() {
+        let text = "name,kind,arrival,departure\n\
+                    IC 1,passenger,08:00,08:05\n\
+                    IC 2,passenger,8h10,08:15\n";
+
+        let error = error_of(from_csv(text, &TrainRegistry::default()));
+
+        assert_eq!(
+            error.to_string(),
+            "line 3, field 'arrival': '8h10' is not a time of day (HH:MM)"
+        );
+    }
+
+    #[test]
+    fn csv_without_a_departure_column_is_refused() {
+        let text = "name,kind,arrival\nIC 1,passenger,08:00\n";
+
+        let error = error_of(from_csv(text, &TrainRegistry::default()));
+
+        assert_eq!(
+            error.to_string(),
+            "line 1, field 'departure': missing column"
+        );
+    }
+
+    #[test]
+    fn csv_kind_the_registry_does_not_know_is_refused() {
+        let text = "name,kind,arrival,departure\nMT 1,maglev,08:00,08:05\n";
+
+        let error = error_of(from_csv(text, &TrainRegistry::default()));
+
+        assert_eq!(
+            error.to_string(),
+            "line 2, field 'kind': unknown kind of train 'maglev'"
+        );
+    }
+
+    #[test]
+    fn toml_platform_which_is_not_a_platform_number_is_reported_with_its_line() {
+        let text = r#"
+[[train]]
+name = "IC 1"
+kind = "passenger"
+arrival = "08:00"
+departure = "08:05"
+platform = -1
+"#;
+
+        let error = error_of(from_toml(text, &TrainRegistry::default()));
+
+        assert_eq!(
+            error.to_string(),
+            "line 7, field 'platform': '-1' is not a platform number"
+        );
+    }
+
+    #[test]
+    fn toml_syntax_error_is_reported_with_its_line() {
+        let text = r#"
+[[train]]
+name = "IC 1"
+kind = "passenger
+arrival = "08:00"
+"#;
+
+        let error = error_of(from_toml(text, &TrainRegistry::default()));
+
+        assert!(
+            matches!(
+                error,
+                TimetableError::Parse {
+                    line: 4,
+                    field: None,
+                    ..
+                }
+            ),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::station::{Arrival, Departure, StationError, StationMediator};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrainKind {
+    Passenger,
+    Freight,
+}
+
+impl fmt::Display for TrainKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Passenger => write!(f, "passenger"),
+            Self::Freight => write!(f, "freight"),
+        }
+    }
+}
+
+/// Everything needed to build a train again, e.g. from a saved station.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrainSpec {
+    pub name: String,
+    pub kind: TrainKind,
+    pub express: bool,
+    pub cars: u32,
+}
+
+impl TrainSpec {
+    pub fn of(train: &dyn Train) -> Self {
+        Self {
+            name: train.name().to_string(),
+            kind: train.kind(),
+            express: train.is_express(),
+            cars: train.cars(),
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Train> {
+        let name = self.name.clone();
+        match (self.kind, self.express) {
+            (TrainKind::Passenger, false) => {
+                Box::new(PassengerTrain::new(name).with_cars(self.cars))
+            }
+            (TrainKind::Passenger, true) => {
+                Box::new(PassengerTrain::express(name).with_cars(self.cars))
+            }
+            (TrainKind::Freight, false) => Box::new(FreightTrain::new(name).with_cars(self.cars)),
+            (TrainKind::Freight, true) => {
+                Box::new(FreightTrain::express(name).with_cars(self.cars))
+            }
+        }
+    }
+}
+
+/// Length of a locomotive in metres.
+const LOCOMOTIVE_LENGTH: u32 = 20;
+
+pub trait Train {
+    fn name(&self) -> &str;
+    fn kind(&self) -> TrainKind;
+    fn is_express(&self) -> bool;
+    fn cars(&self) -> u32;
+    /// Length in metres.
+    fn length(&self) -> u32;
+
+    /// Passenger trains go before freight trains, express trains before local ones.
+    fn priority(&self) -> u32 {
+        let kind = match self.kind() {
+            TrainKind::Passenger => 2,
+            TrainKind::Freight => 0,
+        };
+
+        kind + u32::from(self.is_express())
+    }
+
+    fn arrive(&mut self, mediator: &mut dyn StationMediator);
+    fn depart(&mut self, mediator: &mut dyn StationMediator) -> Result<(), StationError>;
+}
+
+pub struct FreightTrain {
+    name: String,
+    express: bool,
+    cars: u32,
+}
+
+impl FreightTrain {
+    const CARS: u32 = 20;
+    /// Length of a wagon in metres.
+    const CAR_LENGTH: u32 = 15;
+
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            express: false,
+            cars: Self::CARS,
+        }
+    }
+
+    pub fn express(name: String) -> Self {
+        Self {
+            express: true,
+            ..Self::new(name)
+        }
+    }
+
+    pub fn with_cars(mut self, cars: u32) -> Self {
+        self.cars = cars;
+        self
+    }
+}
+
+impl Train for FreightTrain {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> TrainKind {
+        TrainKind::Freight
+    }
+
+    fn is_express(&self) -> bool {
+        self.express
+    }
+
+    fn cars(&self) -> u32 {
+        self.cars
+    }
+
+    fn length(&self) -> u32 {
+        LOCOMOTIVE_LENGTH.saturating_add(self.cars.saturating_mul(Self::CAR_LENGTH))
+    }
+
+    fn arrive(&mut self, mediator: &mut dyn StationMediator) {
+        mediator.notify(Arrival(self));
+    }
+
+    fn depart(&mut self, mediator: &mut dyn StationMediator) -> Result<(), StationError> {
+        mediator.request(Departure(self))
+    }
+}
+
+pub struct PassengerTrain {
+    name: String,
+    express: bool,
+    cars: u32,
+}
+
+impl PassengerTrain {
+    const CARS: u32 = 6;
+    /// Length of a coach in metres.
+    const CAR_LENGTH: u32 = 26;
+
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            express: false,
+            cars: Self::CARS,
+        }
+    }
+
+    pub fn express(name: String) -> Self {
+        Self {
+            express: true,
+            ..Self::new(name)
+        }
+    }
+
+    pub fn with_cars(mut self, cars: u32) -> Self {
+        self.cars = cars;
+        self
+    }
+}
+
+impl Train for PassengerTrain {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> TrainKind {
+        TrainKind::Passenger
+    }
+
+    fn is_express(&self) -> bool {
+        self.express
+    }
+
+    fn cars(&self) -> u32 {
+        self.cars
+    }
+
+    fn length(&self) -> u32 {
+        LOCOMOTIVE_LENGTH.saturating_add(self.cars.saturating_mul(Self::CAR_LENGTH))
+    }
+
+    fn arrive(&mut self, mediator: &mut dyn StationMediator) {
+        mediator.notify(Arrival(self));
+    }
+
+    fn depart(&mut self, mediator: &mut dyn StationMediator) -> Result<(), StationError> {
+        mediator.request(Departure(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_of_a_very_long_train_does_not_overflow() {
+        let freight = FreightTrain::new("CT 1".to_string()).with_cars(u32::MAX);
+        let passenger = PassengerTrain::new("RB 2".to_string()).with_cars(u32::MAX);
+
+        assert_eq!(freight.length(), u32::MAX);
+        assert_eq!(passenger.length(), u32::MAX);
+    }
+}